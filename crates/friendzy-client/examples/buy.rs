@@ -1,9 +1,7 @@
 use solana_client::rpc_client::RpcClient;
-use anchor_spl::token::spl_token;
 use anchor_lang::AnchorDeserialize;
-use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account_idempotent};
 use friendzy_client::*;
-use solana_sdk::{signer::Signer, pubkey::Pubkey, signature::Keypair, transaction::Transaction, message::Message};
+use solana_sdk::{signer::Signer, pubkey::Pubkey, signature::Keypair};
 use std::{str::FromStr, path::Path, fs::File, io::Read};
 
 /// The length in bytes of a keypair, to match the underlying Ed25519 Keypair.
@@ -65,47 +63,26 @@ async fn main() {
     let id = 1436880221354045450;
     let _ = Pubkey::from_str("Gf3sbc5Jb62jH7WcTr3WSNGDQLk1w6wcKMZXKK1SC1E6").unwrap();
 
-    let (bank, _) = derive_bank_address();
-    let (mint, _) = derive_mint_address(id);
-    let (config, _) = derive_config_address(id);
-
     // if we use stacc's pubkey to derive the profile it fails with incorrect pubkey ??
     let (profile, _) = derive_profile_address(id, &keypair.pubkey());
-    let (metadata, _) = derive_metadata_address(&mint);
-
-    let token_account = get_associated_token_address(&keypair.pubkey(), &mint);
-
-    let ix = swap(
-        &keypair.pubkey(),
-        &bank,
-        &config,
-        &mint,
-        &profile,
-        &metadata,
-        &token_account,
-        false,
-        id,
-        1_000_000_000,
-        100_000_000,
-        Side::Buy,
-    );
-
-    println!("{:?}", ix);
-
-    let ixs = [
-        create_associated_token_account_idempotent(
-            &keypair.pubkey(),
-            &keypair.pubkey(),
-            &mint,
-            &spl_token::id(),
-        ),
-        ix
-    ];
-    let blockhash = rpc_client.get_latest_blockhash().unwrap();
 
-    let message = Message::new(&ixs, Some(&keypair.pubkey()));
-    let mut tx = Transaction::new_unsigned(message);
-    tx.partial_sign(&[&keypair], blockhash);
+    let blockhash = rpc_client.get_latest_blockhash().unwrap();
+    let tx = TransactionBuilder::new(keypair.pubkey())
+        .compute_unit_limit(200_000)
+        .compute_unit_price(10_000)
+        .blockhash(blockhash)
+        .swap(
+            &FriendzyProgram::MAINNET,
+            id,
+            1_000_000_000,
+            100_000_000,
+            Side::Buy,
+            false,
+        )
+        .build(&[&keypair])
+        .unwrap();
+
+    println!("{:?}", tx);
 
     rpc_client.send_and_confirm_transaction(&tx).unwrap();
 
@@ -0,0 +1,8 @@
+use friendzy_client::idl;
+
+/// Prints the IDL of the Friendzy program.
+///
+/// Usage: `cargo run --example idl --features serde > idl/friendzy.json`
+fn main() {
+    println!("{}", serde_json::to_string_pretty(&idl()).unwrap());
+}
@@ -0,0 +1,182 @@
+use anchor_lang::solana_program::system_program;
+use base64::{engine::general_purpose, Engine};
+use friendzy_client::*;
+use serde_json::{json, Map, Value};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, message::VersionedMessage, pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{UiLoadedAddresses, UiTransactionEncoding};
+use std::{env, str::FromStr};
+
+/// The names of the swap and withdraw accounts that can be re-derived, by position.
+const NAMED_ACCOUNTS: [(usize, &str); 6] = [
+    (0, "user"),
+    (1, "bank"),
+    (2, "config"),
+    (3, "mint"),
+    (4, "profile"),
+    (8, "metadata"),
+];
+
+fn expected(data: &[u8]) -> Value {
+    match data.get(9) {
+        Some(0) => {
+            let args = VerifyArgs::try_from_slice(data).unwrap();
+            json!({ "kind": "verify", "id": args.id.to_string(), "owner": args.owner.to_string() })
+        }
+        Some(1) | Some(2) => {
+            let args = SwapArgs::try_from_slice(data).unwrap();
+            json!({
+                "kind": "swap",
+                "id": args.id.to_string(),
+                "side": format!("{:?}", args.side),
+                "amount": args.amount.to_string(),
+                "price": args.price.to_string(),
+                "extra": args.extra.map(|extra| extra.to_string()),
+            })
+        }
+        Some(3) => {
+            let args = WithdrawArgs::try_from_slice(data).unwrap();
+            json!({ "kind": "withdraw", "id": args.id.to_string() })
+        }
+        _ => panic!("unknown instruction data {:?}", data),
+    }
+}
+
+/// The account keys of the message in index order, the static keys followed by the writable and
+/// readonly addresses loaded from lookup tables.
+fn account_keys(message: &VersionedMessage, loaded: Option<&UiLoadedAddresses>) -> Vec<Pubkey> {
+    let mut keys = message.static_account_keys().to_vec();
+    if let Some(loaded) = loaded {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .map(|key| Pubkey::from_str(key).unwrap()),
+        );
+    }
+    keys
+}
+
+/// Records a Friendzy transaction as a fixture for `tests/fixtures.rs`.
+///
+/// Usage: `record_fixture <signature> <scenario>`, the fixture is printed to stdout.
+///
+/// The transaction is stored as returned by `getTransaction`. RPC nodes don't serve historical
+/// account state, the config and profile accounts are stored as of `accounts_slot`, when the
+/// fixture is recorded, not as the transaction left them.
+fn main() {
+    let mut args = env::args().skip(1);
+    let signature = Signature::from_str(&args.next().expect("missing signature")).unwrap();
+    let scenario = args.next().expect("missing scenario");
+    let rpc_url =
+        env::var("RPC_URL").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+
+    let rpc_client = RpcClient::new(rpc_url);
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let encoded = rpc_client
+        .get_transaction_with_config(&signature, config)
+        .unwrap();
+    let tx = encoded.transaction.transaction.decode().unwrap();
+    let loaded = encoded
+        .transaction
+        .meta
+        .as_ref()
+        .and_then(|meta| Option::from(meta.loaded_addresses.as_ref()));
+    let keys = account_keys(&tx.message, loaded);
+
+    let mut instructions = vec![];
+    let mut touched: Vec<Pubkey> = vec![];
+    for ix in tx.message.instructions() {
+        if keys[ix.program_id_index as usize] != friendzy_client::id() {
+            continue;
+        }
+        let expected = expected(&ix.data);
+
+        let mut accounts = Map::new();
+        let mut first_purchase = Value::Null;
+        if expected["kind"] != "verify" {
+            for (position, name) in NAMED_ACCOUNTS {
+                let Some(index) = ix.accounts.get(position) else {
+                    continue;
+                };
+                let key = keys[*index as usize];
+                if name == "metadata" {
+                    first_purchase = json!(key != system_program::ID);
+                    if key == system_program::ID {
+                        continue;
+                    }
+                }
+                accounts.insert(name.to_string(), json!(key.to_string()));
+            }
+            touched.extend(
+                [2, 4]
+                    .iter()
+                    .filter_map(|p| ix.accounts.get(*p))
+                    .map(|i| keys[*i as usize]),
+            );
+        }
+
+        // the ordered account list, the harness rebuilds the instruction from it
+        let account_keys: Vec<Value> = ix
+            .accounts
+            .iter()
+            .map(|index| {
+                let index = *index as usize;
+                json!({
+                    "pubkey": keys[index].to_string(),
+                    "signer": tx.message.is_signer(index),
+                    "writable": tx.message.is_maybe_writable(index),
+                })
+            })
+            .collect();
+
+        instructions.push(json!({
+            "data": general_purpose::STANDARD.encode(&ix.data),
+            "first_purchase": first_purchase,
+            "account_keys": account_keys,
+            "accounts": accounts,
+            "expected": expected,
+        }));
+    }
+
+    touched.sort();
+    touched.dedup();
+
+    // the current state of the config and profile accounts the transaction wrote to
+    let response = rpc_client
+        .get_multiple_accounts_with_commitment(&touched, CommitmentConfig::confirmed())
+        .unwrap();
+    let accounts: Vec<Value> = response
+        .value
+        .into_iter()
+        .zip(touched.iter())
+        .filter_map(|(account, pubkey)| {
+            let account = account?;
+            Some(json!({
+                "pubkey": pubkey.to_string(),
+                "account": {
+                    "data": [general_purpose::STANDARD.encode(&account.data), "base64"],
+                    "owner": account.owner.to_string(),
+                },
+            }))
+        })
+        .collect();
+
+    let fixture = json!({
+        "scenario": scenario,
+        "source": format!("https://solscan.io/tx/{}", signature),
+        "transaction": encoded,
+        "instructions": instructions,
+        "accounts_slot": response.context.slot,
+        "accounts": accounts,
+    });
+    println!("{}", serde_json::to_string_pretty(&fixture).unwrap());
+}
@@ -0,0 +1,376 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use std::collections::HashMap;
+
+use crate::{fees::FeeSchedule, fees::Quote, Side, SwapArgs, DECIMAL_DENOMINATOR};
+
+/// A swap decoded from the chain, the input of a backtest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeEvent {
+    pub slot: u64,
+    /// The position of the transaction in the block, orders trades within a slot.
+    pub transaction_index: u32,
+    /// The unix timestamp of the block.
+    pub block_time: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::u64_string"))]
+    pub id: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::pubkey_string"))]
+    pub trader: Pubkey,
+    pub side: Side,
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::u64_string"))]
+    pub amount: u64,
+}
+
+impl TradeEvent {
+    pub fn from_swap(
+        slot: u64,
+        transaction_index: u32,
+        block_time: i64,
+        trader: Pubkey,
+        args: &SwapArgs,
+    ) -> Self {
+        Self {
+            slot,
+            transaction_index,
+            block_time,
+            id: args.id,
+            trader,
+            side: args.side,
+            amount: args.amount,
+        }
+    }
+
+    /// Decodes a swap instruction, the trader is the signer at position 0.
+    pub fn from_instruction(
+        slot: u64,
+        transaction_index: u32,
+        block_time: i64,
+        instruction: &Instruction,
+    ) -> Option<Self> {
+        let args = SwapArgs::try_from_slice(&instruction.data).ok()?;
+        let trader = instruction.accounts.first()?.pubkey;
+        Some(Self::from_swap(
+            slot,
+            transaction_index,
+            block_time,
+            trader,
+            &args,
+        ))
+    }
+}
+
+/// The supply of an id, including the keys bought by the strategy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurveState {
+    pub id: u64,
+    pub supply: u64,
+}
+
+impl CurveState {
+    /// The curve cost of the next whole key.
+    pub fn price(&self) -> Option<u64> {
+        crate::calculate_buy_cost(self.supply, DECIMAL_DENOMINATOR)
+    }
+}
+
+/// A swap requested by a strategy, filled right after the trade that triggered it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Order {
+    pub id: u64,
+    pub side: Side,
+    pub amount: u64,
+}
+
+pub trait Strategy {
+    /// Called after every historical trade with the updated curve and the strategy's position
+    /// in that id, both in native units.
+    fn on_trade(&mut self, trade: &TradeEvent, curve: &CurveState, position: u64) -> Vec<Order>;
+}
+
+/// A filled order of the strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    /// The index in the history of the trade that triggered the order.
+    pub trade: usize,
+    pub id: u64,
+    pub quote: Quote,
+}
+
+/// The result of a backtest, amounts are in lamports.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BacktestReport {
+    pub fills: Vec<Fill>,
+    /// The lamports received from sells minus the lamports paid for buys.
+    pub cash: i128,
+    /// The royalties and protocol fees paid on every fill.
+    pub fees_paid: u64,
+    /// The keys held at the end, by id.
+    pub positions: HashMap<u64, u64>,
+    /// The cash plus what the positions would sell for at the end.
+    pub pnl: i128,
+    /// The largest drop of the marked-to-market equity from a previous peak.
+    pub max_drawdown: u128,
+}
+
+/// Replays a trade history through the exact curve and fee math.
+///
+/// Trades are replayed in slot and transaction index order, whatever the order of the history.
+/// Orders move the supply like any other trade, so fills of the strategy move the price for the
+/// trades that follow. Sells are capped at the position and orders the curve can't quote are
+/// skipped. Positions are marked at what selling them would return, fees included.
+#[derive(Debug, Clone)]
+pub struct Backtester {
+    fees: FeeSchedule,
+    supplies: HashMap<u64, u64>,
+}
+
+impl Backtester {
+    pub fn new(fees: FeeSchedule) -> Self {
+        Self {
+            fees,
+            supplies: HashMap::new(),
+        }
+    }
+
+    /// Sets the supply of an id before its first trade in the history, the default is zero.
+    pub fn with_supply(mut self, id: u64, supply: u64) -> Self {
+        self.supplies.insert(id, supply);
+        self
+    }
+
+    pub fn run<S: Strategy + ?Sized>(
+        &self,
+        strategy: &mut S,
+        trades: &[TradeEvent],
+    ) -> BacktestReport {
+        let mut supplies = self.supplies.clone();
+        let mut report = BacktestReport::default();
+        let mut peak = 0;
+
+        let mut indexes: Vec<usize> = (0..trades.len()).collect();
+        indexes.sort_by_key(|index| (trades[*index].slot, trades[*index].transaction_index));
+
+        for index in indexes {
+            let trade = &trades[index];
+            let supply = supplies.entry(trade.id).or_default();
+            *supply = match trade.side {
+                Side::Buy => supply.saturating_add(trade.amount),
+                Side::Sell => supply.saturating_sub(trade.amount),
+                Side::Default => *supply,
+            };
+
+            let curve = CurveState {
+                id: trade.id,
+                supply: *supply,
+            };
+            let position = report.positions.get(&trade.id).copied().unwrap_or_default();
+
+            for order in strategy.on_trade(trade, &curve, position) {
+                self.fill(index, order, &mut supplies, &mut report);
+            }
+
+            let equity = report.cash + self.liquidation_value(&supplies, &report.positions);
+            peak = peak.max(equity);
+            report.max_drawdown = report.max_drawdown.max((peak - equity) as u128);
+        }
+
+        report.pnl = report.cash + self.liquidation_value(&supplies, &report.positions);
+        report
+    }
+
+    fn fill(
+        &self,
+        trade: usize,
+        order: Order,
+        supplies: &mut HashMap<u64, u64>,
+        report: &mut BacktestReport,
+    ) {
+        let supply = supplies.entry(order.id).or_default();
+        let position = report.positions.entry(order.id).or_default();
+        let amount = match order.side {
+            Side::Sell => order.amount.min(*position),
+            _ => order.amount,
+        };
+        if amount == 0 {
+            return;
+        }
+        let Some(quote) = self.fees.quote(order.side, *supply, amount) else {
+            return;
+        };
+
+        match order.side {
+            Side::Sell => {
+                *supply -= amount;
+                *position -= amount;
+                report.cash += quote.total() as i128;
+            }
+            _ => {
+                *supply += amount;
+                *position += amount;
+                report.cash -= quote.total() as i128;
+            }
+        }
+        report.fees_paid += quote.fees();
+        report.fills.push(Fill {
+            trade,
+            id: order.id,
+            quote,
+        });
+    }
+
+    fn liquidation_value(
+        &self,
+        supplies: &HashMap<u64, u64>,
+        positions: &HashMap<u64, u64>,
+    ) -> i128 {
+        positions
+            .iter()
+            .filter(|(_, amount)| **amount > 0)
+            .filter_map(|(id, amount)| {
+                let supply = supplies.get(id).copied().unwrap_or_default();
+                self.fees.quote(Side::Sell, supply, *amount)
+            })
+            .map(|quote| quote.total() as i128)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+
+    const FEES: FeeSchedule = FeeSchedule {
+        royalty_bps: 500,
+        protocol_bps: 500,
+    };
+
+    /// Buys one key on the first trade of an id and sells it once the supply grew by `target`.
+    struct Momentum {
+        target: u64,
+        entries: HashMap<u64, u64>,
+    }
+
+    impl Strategy for Momentum {
+        fn on_trade(
+            &mut self,
+            trade: &TradeEvent,
+            curve: &CurveState,
+            position: u64,
+        ) -> Vec<Order> {
+            match self.entries.get(&trade.id) {
+                None => {
+                    self.entries.insert(trade.id, curve.supply);
+                    vec![Order {
+                        id: trade.id,
+                        side: Side::Buy,
+                        amount: DECIMAL_DENOMINATOR,
+                    }]
+                }
+                Some(entry) if position > 0 && curve.supply >= entry + self.target => vec![Order {
+                    id: trade.id,
+                    side: Side::Sell,
+                    amount: position,
+                }],
+                _ => vec![],
+            }
+        }
+    }
+
+    fn trade(slot: u64, side: Side, keys: u64) -> TradeEvent {
+        TradeEvent {
+            slot,
+            transaction_index: 0,
+            block_time: slot as i64,
+            id: 42,
+            trader: Pubkey::default(),
+            side,
+            amount: keys * DECIMAL_DENOMINATOR,
+        }
+    }
+
+    #[test]
+    fn test_backtest_profit() -> Result<()> {
+        let trades: Vec<TradeEvent> = (0..20).map(|slot| trade(slot, Side::Buy, 5)).collect();
+        let mut strategy = Momentum {
+            target: 50 * DECIMAL_DENOMINATOR,
+            entries: HashMap::new(),
+        };
+
+        let report = Backtester::new(FEES).run(&mut strategy, &trades);
+
+        assert_eq!(2, report.fills.len());
+        assert_eq!(Side::Buy, report.fills[0].quote.side);
+        assert_eq!(DECIMAL_DENOMINATOR, report.fills[0].quote.amount);
+        assert_eq!(Side::Sell, report.fills[1].quote.side);
+        assert_eq!(0, report.positions[&42]);
+
+        let fees: u64 = report.fills.iter().map(|fill| fill.quote.fees()).sum();
+        assert_eq!(fees, report.fees_paid);
+        assert_eq!(report.cash, report.pnl);
+        assert!(report.pnl > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backtest_sorts_trades() -> Result<()> {
+        let trades: Vec<TradeEvent> = (0..20).map(|slot| trade(slot, Side::Buy, 5)).collect();
+        let mut reversed = trades.clone();
+        reversed.reverse();
+        let strategy = || Momentum {
+            target: 50 * DECIMAL_DENOMINATOR,
+            entries: HashMap::new(),
+        };
+
+        let report = Backtester::new(FEES).run(&mut strategy(), &trades);
+        let unsorted = Backtester::new(FEES).run(&mut strategy(), &reversed);
+
+        assert_eq!(report.pnl, unsorted.pnl);
+        assert_eq!(report.max_drawdown, unsorted.max_drawdown);
+        // fills point into the history as given
+        assert_eq!(0, report.fills[0].trade);
+        assert_eq!(19, unsorted.fills[0].trade);
+
+        let swap = SwapArgs {
+            id: 42,
+            side: Side::Buy,
+            amount: 5 * DECIMAL_DENOMINATOR,
+            ..SwapArgs::default()
+        };
+        let instruction = Instruction {
+            program_id: crate::id(),
+            accounts: vec![AccountMeta::new(Pubkey::default(), true)],
+            data: swap.to_instruction_data(),
+        };
+        assert_eq!(
+            Some(trades[3]),
+            TradeEvent::from_instruction(3, 0, 3, &instruction)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backtest_drawdown() -> Result<()> {
+        let mut trades = vec![trade(0, Side::Buy, 10)];
+        trades.extend((1..=5).map(|slot| trade(slot, Side::Sell, 2)));
+        let mut strategy = Momentum {
+            target: u64::MAX / 2,
+            entries: HashMap::new(),
+        };
+
+        let report = Backtester::new(FEES)
+            .with_supply(42, 100 * DECIMAL_DENOMINATOR)
+            .run(&mut strategy, &trades);
+
+        assert_eq!(1, report.fills.len());
+        assert_eq!(DECIMAL_DENOMINATOR, report.positions[&42]);
+        assert!(report.pnl < 0);
+        // the fees are lost as soon as the key is bought, the rest as the supply drops
+        assert!(report.max_drawdown > report.fees_paid as u128);
+        assert_eq!(-report.pnl as u128, report.max_drawdown);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use solana_sdk::{packet::PACKET_DATA_SIZE, transaction::VersionedTransaction};
+
+use crate::{
+    compute::{ComputeEstimator, ComputeVariant, MAX_COMPUTE_UNIT_LIMIT},
+    error::ClientError,
+    program::FriendzyProgram,
+    transaction::TransactionBuilder,
+    Side,
+};
+
+/// The compute units assumed for a leg without an estimate, the default budget per instruction.
+pub const DEFAULT_LEG_UNITS: u32 = 200_000;
+
+/// One swap of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapLeg {
+    pub id: u64,
+    pub amount: u64,
+    pub price: u64,
+    pub side: Side,
+    pub first_purchase: bool,
+}
+
+impl SwapLeg {
+    pub fn buy(id: u64, amount: u64, price: u64) -> Self {
+        Self {
+            id,
+            amount,
+            price,
+            side: Side::Buy,
+            first_purchase: false,
+        }
+    }
+
+    pub fn sell(id: u64, amount: u64, price: u64) -> Self {
+        Self {
+            id,
+            amount,
+            price,
+            side: Side::Sell,
+            first_purchase: false,
+        }
+    }
+}
+
+/// A transaction of a packed batch and the indices of the legs it contains.
+#[derive(Debug, Clone)]
+pub struct BatchTransaction {
+    pub legs: Vec<usize>,
+    pub builder: TransactionBuilder,
+}
+
+/// Packs swaps for several ids into as few transactions as fit the packet size and compute limit.
+///
+/// The template provides the payer, unit price, lookup tables and blockhash of every transaction,
+/// its compute unit limit is replaced by the sum of the legs' estimates, each swap with the
+/// creation of its token account. Accounts shared by the legs, like the bank, vault and programs,
+/// are only included once per transaction.
+#[derive(Debug, Clone)]
+pub struct BatchBuilder {
+    program: FriendzyProgram,
+    template: TransactionBuilder,
+    legs: Vec<SwapLeg>,
+}
+
+impl BatchBuilder {
+    pub fn new(program: FriendzyProgram, template: TransactionBuilder) -> Self {
+        Self {
+            program,
+            template,
+            legs: vec![],
+        }
+    }
+
+    pub fn leg(mut self, leg: SwapLeg) -> Self {
+        self.legs.push(leg);
+        self
+    }
+
+    pub fn legs(&self) -> &[SwapLeg] {
+        &self.legs
+    }
+
+    /// Packs the legs in order, starting a new transaction whenever the next leg doesn't fit.
+    pub fn pack(&self, estimator: &ComputeEstimator) -> Result<Vec<BatchTransaction>, ClientError> {
+        let mut transactions = vec![];
+        let mut current = self.template.clone();
+        let mut legs = vec![];
+        let mut units = 0;
+
+        for (index, leg) in self.legs.iter().enumerate() {
+            let leg_units = ComputeVariant::swap(leg.side, leg.first_purchase)
+                .and_then(|variant| estimator.recommended_leg(variant, leg.first_purchase))
+                .unwrap_or(DEFAULT_LEG_UNITS);
+
+            let candidate = self.add_leg(current.clone(), leg, units + leg_units);
+            if units + leg_units <= MAX_COMPUTE_UNIT_LIMIT && fits(&candidate)? {
+                current = candidate;
+                units += leg_units;
+                legs.push(index);
+                continue;
+            }
+
+            if legs.is_empty() {
+                return Err(ClientError::LegTooLarge(index));
+            }
+            transactions.push(BatchTransaction {
+                legs: std::mem::take(&mut legs),
+                builder: current,
+            });
+
+            let candidate = self.add_leg(self.template.clone(), leg, leg_units);
+            if !fits(&candidate)? {
+                return Err(ClientError::LegTooLarge(index));
+            }
+            current = candidate;
+            units = leg_units;
+            legs.push(index);
+        }
+
+        if !legs.is_empty() {
+            transactions.push(BatchTransaction {
+                legs,
+                builder: current,
+            });
+        }
+
+        Ok(transactions)
+    }
+
+    fn add_leg(
+        &self,
+        builder: TransactionBuilder,
+        leg: &SwapLeg,
+        units: u32,
+    ) -> TransactionBuilder {
+        builder.compute_unit_limit(units).swap(
+            &self.program,
+            leg.id,
+            leg.amount,
+            leg.price,
+            leg.side,
+            leg.first_purchase,
+        )
+    }
+}
+
+/// Whether the serialized transaction fits in a packet.
+fn fits(builder: &TransactionBuilder) -> Result<bool, ClientError> {
+    Ok(transaction_size(&builder.build_unsigned()?) <= PACKET_DATA_SIZE)
+}
+
+/// The size of the serialized transaction, the signatures are prefixed with a compact-u16 length.
+pub fn transaction_size(transaction: &VersionedTransaction) -> usize {
+    let signatures = transaction.signatures.len();
+    let prefix = match signatures {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    };
+    prefix + signatures * 64 + transaction.message.serialize().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        address_lookup_table_account::AddressLookupTableAccount, hash::Hash, pubkey::Pubkey,
+    };
+
+    fn batch(template: TransactionBuilder, count: u64) -> BatchBuilder {
+        (0..count).fold(
+            BatchBuilder::new(FriendzyProgram::MAINNET, template),
+            |batch, id| match id % 2 {
+                0 => batch.leg(SwapLeg::buy(id, 1_000_000_000, 100_000_000)),
+                _ => batch.leg(SwapLeg::sell(id, 1_000_000_000, 0)),
+            },
+        )
+    }
+
+    #[test]
+    fn test_pack_by_size() {
+        let template = TransactionBuilder::new(Pubkey::new_unique())
+            .compute_unit_price(10_000)
+            .blockhash(Hash::new_unique());
+        let transactions = batch(template, 10).pack(&ComputeEstimator::new()).unwrap();
+
+        assert!(transactions.len() > 1);
+        let legs: Vec<usize> = transactions.iter().flat_map(|tx| tx.legs.clone()).collect();
+        assert_eq!((0..10).collect::<Vec<usize>>(), legs);
+
+        for transaction in &transactions {
+            let tx = transaction.builder.build_unsigned().unwrap();
+            assert!(transaction_size(&tx) <= PACKET_DATA_SIZE);
+            assert_eq!(
+                bincode::serialize(&tx).unwrap().len(),
+                transaction_size(&tx)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pack_by_compute() {
+        let mut estimator = ComputeEstimator::with_margin(0);
+        estimator.insert(ComputeVariant::Buy, 600_000);
+        estimator.insert(ComputeVariant::Sell, 600_000);
+        estimator.insert(ComputeVariant::CreateTokenAccount, 20_000);
+
+        let payer = Pubkey::new_unique();
+        let program = FriendzyProgram::MAINNET;
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: (0..4)
+                .flat_map(|id| {
+                    let addresses = crate::FriendzyAddresses::for_id(id);
+                    [
+                        addresses.mint,
+                        addresses.config,
+                        addresses.metadata,
+                        program.derive_profile_address(id, &payer).0,
+                    ]
+                })
+                .collect(),
+        };
+        let template = TransactionBuilder::new(payer).lookup_table(table);
+
+        let transactions = batch(template, 4).pack(&estimator).unwrap();
+        assert_eq!(
+            vec![vec![0, 1], vec![2, 3]],
+            transactions
+                .iter()
+                .map(|tx| tx.legs.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+}
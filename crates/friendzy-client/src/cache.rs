@@ -0,0 +1,235 @@
+use anchor_lang::prelude::*;
+use std::{collections::HashMap, thread};
+
+use crate::program::FriendzyProgram;
+
+/// Every address derived from an X id, along with the bump used to derive it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FriendzyAddresses {
+    pub id: u64,
+    pub mint: Pubkey,
+    pub mint_bump: u8,
+    pub config: Pubkey,
+    pub config_bump: u8,
+    pub metadata: Pubkey,
+    pub metadata_bump: u8,
+}
+
+impl FriendzyAddresses {
+    /// Derives every mainnet address for the given id.
+    pub fn for_id(id: u64) -> Self {
+        Self::for_program(&FriendzyProgram::MAINNET, id)
+    }
+
+    /// Derives every address for the given id, this performs three `find_program_address` calls.
+    pub fn for_program(program: &FriendzyProgram, id: u64) -> Self {
+        let (mint, mint_bump) = program.derive_mint_address(id);
+        let (config, config_bump) = program.derive_config_address(id);
+        let (metadata, metadata_bump) = program.derive_metadata_address(&mint);
+
+        Self {
+            id,
+            mint,
+            mint_bump,
+            config,
+            config_bump,
+            metadata,
+            metadata_bump,
+        }
+    }
+
+    /// Rebuilds the addresses from previously found bumps without searching for them again.
+    pub fn from_bumps(
+        program: &FriendzyProgram,
+        id: u64,
+        mint_bump: u8,
+        config_bump: u8,
+        metadata_bump: u8,
+    ) -> Result<Self> {
+        let mint = program.create_mint_address(id, mint_bump)?;
+        let config = program.create_config_address(id, config_bump)?;
+        let metadata = program.create_metadata_address(&mint, metadata_bump)?;
+
+        Ok(Self {
+            id,
+            mint,
+            mint_bump,
+            config,
+            config_bump,
+            metadata,
+            metadata_bump,
+        })
+    }
+}
+
+/// Memoises the addresses derived for each id and each (id, user) profile.
+#[derive(Debug, Default, Clone)]
+pub struct AddressCache {
+    program: FriendzyProgram,
+    addresses: HashMap<u64, FriendzyAddresses>,
+    profiles: HashMap<(u64, Pubkey), (Pubkey, u8)>,
+}
+
+impl AddressCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_program(program: FriendzyProgram) -> Self {
+        Self {
+            program,
+            ..Default::default()
+        }
+    }
+
+    pub fn program(&self) -> &FriendzyProgram {
+        &self.program
+    }
+
+    /// Returns the addresses for the id, deriving and storing them on first use.
+    pub fn get(&mut self, id: u64) -> &FriendzyAddresses {
+        let program = &self.program;
+        self.addresses
+            .entry(id)
+            .or_insert_with(|| FriendzyAddresses::for_program(program, id))
+    }
+
+    /// Returns the profile address and bump for the id and user, deriving and storing them on first use.
+    pub fn profile(&mut self, id: u64, user: &Pubkey) -> (Pubkey, u8) {
+        let program = &self.program;
+        *self
+            .profiles
+            .entry((id, *user))
+            .or_insert_with(|| program.derive_profile_address(id, user))
+    }
+
+    pub fn insert(&mut self, addresses: FriendzyAddresses) {
+        self.addresses.insert(addresses.id, addresses);
+    }
+
+    /// Derives and stores the addresses for every id not yet in the cache, in parallel.
+    pub fn warm(&mut self, ids: &[u64]) {
+        let missing: Vec<u64> = ids
+            .iter()
+            .filter(|id| !self.addresses.contains_key(id))
+            .copied()
+            .collect();
+
+        for addresses in derive_addresses_batch(&self.program, &missing) {
+            self.insert(addresses);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+/// Derives the addresses for many ids, splitting the work across the available cores.
+///
+/// The output is in the same order as the given ids.
+pub fn derive_addresses_batch(program: &FriendzyProgram, ids: &[u64]) -> Vec<FriendzyAddresses> {
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk_size = ids.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = ids
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|id| FriendzyAddresses::for_program(program, *id))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_addresses_for_id() -> Result<()> {
+        // https://solscan.io/tx/xuNbLveFGhwknY12fbnrRhCffaj355LucVEZXoudjxhESB1BVi27Z2bPrqQcZirSHktkCSM3dVqHWrojB8ZyzQk
+        let addresses = FriendzyAddresses::for_id(1_162_302_698_118_684_672);
+
+        assert_eq!(
+            Pubkey::from_str("GUZJcmy4QRF3dXWcRxueyPGQfQAmRs1FtqiszRJaFfxV").unwrap(),
+            addresses.mint
+        );
+        assert_eq!(
+            Pubkey::from_str("5Y3ac7p37XtSMmbb84QuP8dZTv3tfuH2pu1Wg6dokwDQ").unwrap(),
+            addresses.config
+        );
+        assert_eq!(
+            Pubkey::from_str("8FQs4Z7HDJG7LetoUkcBAnvEKJZaTNcurzbH6o6sxwPD").unwrap(),
+            addresses.metadata
+        );
+
+        let from_bumps = FriendzyAddresses::from_bumps(
+            &FriendzyProgram::MAINNET,
+            addresses.id,
+            addresses.mint_bump,
+            addresses.config_bump,
+            addresses.metadata_bump,
+        )?;
+        assert_eq!(addresses, from_bumps);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_cache() -> Result<()> {
+        let user = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+        let mut cache = AddressCache::new();
+        assert!(cache.is_empty());
+
+        cache.warm(&[1, 2, 3, 1_162_302_698_118_684_672]);
+        assert_eq!(4, cache.len());
+
+        let config = cache.get(1_162_302_698_118_684_672).config;
+        assert_eq!(
+            Pubkey::from_str("5Y3ac7p37XtSMmbb84QuP8dZTv3tfuH2pu1Wg6dokwDQ").unwrap(),
+            config
+        );
+
+        let (profile, _) = cache.profile(1_162_302_698_118_684_672, &user);
+        assert_eq!(
+            Pubkey::from_str("74zFAk5CPA9SNmJPD2K7DqS8WMP1cG9G7DbKN1vyYiVd").unwrap(),
+            profile
+        );
+
+        cache.get(4);
+        assert_eq!(5, cache.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_addresses_batch_preserves_order() -> Result<()> {
+        let ids: Vec<u64> = (0..64).collect();
+        let batch = derive_addresses_batch(&FriendzyProgram::MAINNET, &ids);
+
+        assert_eq!(ids.len(), batch.len());
+        for (id, addresses) in ids.iter().zip(batch.iter()) {
+            assert_eq!(FriendzyAddresses::for_id(*id), *addresses);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+
+use crate::{
+    error::ClientError, fees::BPS_DENOMINATOR, program::FriendzyProgram,
+    transaction::TransactionBuilder, Side,
+};
+
+/// The maximum compute unit limit a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// The paths through the program that consume a different number of compute units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComputeVariant {
+    /// A buy that creates the mint, metadata and config, CPIs into Metaplex.
+    FirstPurchase,
+    Buy,
+    Sell,
+    Withdraw,
+    /// The idempotent creation of the token account that precedes a swap other than a first
+    /// purchase.
+    CreateTokenAccount,
+}
+
+impl ComputeVariant {
+    /// Returns the variant of a swap, `None` for [`Side::Default`].
+    pub fn swap(side: Side, first_purchase: bool) -> Option<Self> {
+        match (side, first_purchase) {
+            (Side::Buy, true) => Some(ComputeVariant::FirstPurchase),
+            (Side::Buy, false) => Some(ComputeVariant::Buy),
+            (Side::Sell, _) => Some(ComputeVariant::Sell),
+            (Side::Default, _) => None,
+        }
+    }
+}
+
+/// Simulates a transaction and returns the compute units it consumed.
+pub trait Simulate {
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError>;
+}
+
+impl Simulate for RpcClient {
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError> {
+        let result = self
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+
+        if let Some(err) = result.err {
+            return Err(ClientError::Simulation(err));
+        }
+        result
+            .units_consumed
+            .ok_or(ClientError::MissingUnitsConsumed)
+    }
+}
+
+/// Estimates compute unit limits by simulation, caching the units consumed per variant.
+///
+/// A variant's units are those of its instruction alone, measured by simulating the transaction
+/// with and without it, so the compute budget, nonce and other instructions of the transaction
+/// are not counted. The recommended limit is the units consumed plus a margin in basis points,
+/// the default is 20%.
+#[derive(Debug, Clone)]
+pub struct ComputeEstimator {
+    margin_bps: u64,
+    units: HashMap<ComputeVariant, u64>,
+}
+
+impl Default for ComputeEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComputeEstimator {
+    pub fn new() -> Self {
+        Self::with_margin(2_000)
+    }
+
+    pub fn with_margin(margin_bps: u64) -> Self {
+        Self {
+            margin_bps,
+            units: HashMap::new(),
+        }
+    }
+
+    /// Records the units consumed by a variant, e.g. from a previous run.
+    pub fn insert(&mut self, variant: ComputeVariant, units: u64) {
+        self.units.insert(variant, units);
+    }
+
+    /// Returns the cached units consumed by a variant.
+    pub fn units(&self, variant: ComputeVariant) -> Option<u64> {
+        self.units.get(&variant).copied()
+    }
+
+    /// Returns the recommended limit for a variant, if it was estimated before.
+    pub fn recommended(&self, variant: ComputeVariant) -> Option<u32> {
+        self.units(variant)
+            .map(|units| self.with_margin_applied(units))
+    }
+
+    fn with_margin_applied(&self, units: u64) -> u32 {
+        let units =
+            units as u128 * (BPS_DENOMINATOR + self.margin_bps) as u128 / BPS_DENOMINATOR as u128;
+        units.min(MAX_COMPUTE_UNIT_LIMIT as u128) as u32
+    }
+
+    /// Returns the recommended limit for a swap, with the creation of its token account unless
+    /// it's a first purchase, if both were estimated before.
+    pub fn recommended_leg(&self, variant: ComputeVariant, first_purchase: bool) -> Option<u32> {
+        let token_account = match first_purchase {
+            true => 0,
+            false => self.units(ComputeVariant::CreateTokenAccount)?,
+        };
+        Some(self.with_margin_applied(self.units(variant)? + token_account))
+    }
+
+    /// Returns the recommended limit for the program instruction of the builder, simulating the
+    /// builder's transaction on a cache miss.
+    ///
+    /// The builder is expected to hold a single instruction of the program, the variant. It's
+    /// simulated with the maximum limit so it can't run out of compute, then without the program
+    /// instruction, the difference is cached. Token account creations are measured the same way
+    /// and cached as [`ComputeVariant::CreateTokenAccount`].
+    pub fn estimate<S: Simulate + ?Sized>(
+        &mut self,
+        simulator: &S,
+        program: &FriendzyProgram,
+        variant: ComputeVariant,
+        builder: &TransactionBuilder,
+    ) -> Result<u32, ClientError> {
+        let creates_token_account = builder
+            .instructions()
+            .iter()
+            .any(|ix| ix.program_id == spl_associated_token_account::id());
+        let missing = |variant| self.units(variant).is_none();
+        if missing(variant) || creates_token_account && missing(ComputeVariant::CreateTokenAccount)
+        {
+            let builder = builder.clone().compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT);
+            let total = simulator.simulate_units(&builder.build_unsigned()?)?;
+            let without_program =
+                builder.retain_instructions(|ix| ix.program_id != program.program_id);
+            let rest = simulator.simulate_units(&without_program.build_unsigned()?)?;
+            self.units.insert(variant, total.saturating_sub(rest));
+
+            if creates_token_account {
+                let baseline = without_program
+                    .retain_instructions(|ix| ix.program_id != spl_associated_token_account::id());
+                let base = simulator.simulate_units(&baseline.build_unsigned()?)?;
+                self.units.insert(
+                    ComputeVariant::CreateTokenAccount,
+                    rest.saturating_sub(base),
+                );
+            }
+        }
+
+        let token_account = match creates_token_account {
+            true => self.units(ComputeVariant::CreateTokenAccount).unwrap_or(0),
+            false => 0,
+        };
+        Ok(self.with_margin_applied(self.units(variant).unwrap_or(0) + token_account))
+    }
+
+    /// Sets the builder's compute unit limit to the estimate for the variant.
+    pub fn apply<S: Simulate + ?Sized>(
+        &mut self,
+        simulator: &S,
+        program: &FriendzyProgram,
+        variant: ComputeVariant,
+        builder: TransactionBuilder,
+    ) -> Result<TransactionBuilder, ClientError> {
+        let limit = self.estimate(simulator, program, variant, &builder)?;
+        Ok(builder.compute_unit_limit(limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::FriendzyProgram;
+    use solana_sdk::pubkey::Pubkey;
+    use std::cell::Cell;
+
+    /// Charges fixed units per instruction depending on its program.
+    struct FixedSimulator {
+        program: FriendzyProgram,
+        units: u64,
+        calls: Cell<usize>,
+    }
+
+    impl Simulate for FixedSimulator {
+        fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError> {
+            assert_eq!(1, transaction.signatures.len());
+            self.calls.set(self.calls.get() + 1);
+
+            let message = &transaction.message;
+            let keys = message.static_account_keys();
+            Ok(message
+                .instructions()
+                .iter()
+                .map(|ix| match keys[ix.program_id_index as usize] {
+                    key if key == self.program.program_id => self.units,
+                    key if key == spl_associated_token_account::id() => 10_000,
+                    _ => 150,
+                })
+                .sum())
+        }
+    }
+
+    #[test]
+    fn test_estimate_is_cached_per_variant() {
+        let program = FriendzyProgram::MAINNET;
+        let simulator = FixedSimulator {
+            program,
+            units: 50_000,
+            calls: Cell::new(0),
+        };
+        let payer = Pubkey::new_unique();
+        let builder = TransactionBuilder::new(payer)
+            .compute_unit_price(10_000)
+            .swap(&program, 42, 1_000_000_000, 100_000_000, Side::Buy, false);
+
+        let mut estimator = ComputeEstimator::new();
+        assert_eq!(None, estimator.recommended(ComputeVariant::Buy));
+
+        // the swap and its token account creation, without the compute budget instructions
+        let variant = ComputeVariant::swap(Side::Buy, false).unwrap();
+        assert_eq!(
+            72_000,
+            estimator
+                .estimate(&simulator, &program, variant, &builder)
+                .unwrap()
+        );
+        assert_eq!(Some(50_000), estimator.units(ComputeVariant::Buy));
+        assert_eq!(
+            Some(10_000),
+            estimator.units(ComputeVariant::CreateTokenAccount)
+        );
+        assert_eq!(Some(72_000), estimator.recommended_leg(variant, false));
+        assert_eq!(Some(60_000), estimator.recommended_leg(variant, true));
+        assert_eq!(
+            72_000,
+            estimator
+                .estimate(&simulator, &program, variant, &builder)
+                .unwrap()
+        );
+        assert_eq!(3, simulator.calls.get());
+
+        let builder = estimator
+            .apply(&simulator, &program, ComputeVariant::Sell, builder)
+            .unwrap();
+        assert_eq!(6, simulator.calls.get());
+        assert_eq!(Some(50_000), estimator.units(ComputeVariant::Sell));
+
+        let message = builder.build_unsigned().unwrap().message;
+        let instruction = &message.instructions()[0];
+        assert_eq!(
+            solana_sdk::compute_budget::id(),
+            message.static_account_keys()[instruction.program_id_index as usize]
+        );
+
+        // a first purchase doesn't create the token account
+        let builder = TransactionBuilder::new(payer).swap(
+            &program,
+            42,
+            1_000_000_000,
+            u64::MAX,
+            Side::Buy,
+            true,
+        );
+        assert_eq!(
+            60_000,
+            estimator
+                .estimate(
+                    &simulator,
+                    &program,
+                    ComputeVariant::FirstPurchase,
+                    &builder
+                )
+                .unwrap()
+        );
+        assert_eq!(8, simulator.calls.get());
+    }
+
+    #[test]
+    fn test_recommended_is_capped() {
+        let mut estimator = ComputeEstimator::with_margin(5_000);
+        estimator.insert(ComputeVariant::FirstPurchase, 1_200_000);
+        estimator.insert(ComputeVariant::Withdraw, 10_000);
+
+        assert_eq!(
+            Some(MAX_COMPUTE_UNIT_LIMIT),
+            estimator.recommended(ComputeVariant::FirstPurchase)
+        );
+        assert_eq!(
+            Some(15_000),
+            estimator.recommended(ComputeVariant::Withdraw)
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use anchor_lang::prelude::Pubkey;
+use std::collections::HashMap;
+
+use crate::{backtest::TradeEvent, fees::FeeSchedule, Config, Side};
+
+/// The seconds in a day, days are UTC.
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// The royalties earned on the trades of a day.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DailyRoyalties {
+    /// The unix timestamp the day starts at.
+    pub day: i64,
+    pub royalties: u64,
+    /// The lamports moved along the curve.
+    pub volume: u64,
+    pub trades: u32,
+}
+
+/// The royalties a trader paid to the creator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraderRoyalties {
+    pub trader: Pubkey,
+    pub royalties: u64,
+    pub trades: u32,
+}
+
+/// The royalties earned by moving the supply from the current one to `supply`, `None` when the
+/// curve can't quote it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoyaltyProjection {
+    pub supply: u64,
+    pub royalties: Option<u64>,
+}
+
+/// The royalty income of the owner of a `Config`, amounts are in lamports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatorReport {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub supply: u64,
+    pub royalties: u64,
+    pub unclaimed: u64,
+    pub claimed_royalties: u64,
+    pub debt: u64,
+    /// The royalties earned per day in the trade history, oldest first.
+    pub daily: Vec<DailyRoyalties>,
+    /// The traders of the history, by royalties paid.
+    pub top_traders: Vec<TraderRoyalties>,
+    pub projections: Vec<RoyaltyProjection>,
+}
+
+impl CreatorReport {
+    /// Builds the report from the config and the trade history of its id.
+    ///
+    /// The history is assumed to end at the current state, the supply before the first trade is
+    /// the config supply minus the net amount traded. Royalties of the history are recomputed
+    /// with the fee schedule, while the totals come from the config.
+    pub fn new(
+        config: &Config,
+        trades: &[TradeEvent],
+        fees: &FeeSchedule,
+        supply_levels: &[u64],
+    ) -> Self {
+        let net = trades
+            .iter()
+            .filter(|t| t.id == config.id)
+            .fold(0i128, |net, t| match t.side {
+                Side::Buy => net + t.amount as i128,
+                Side::Sell => net - t.amount as i128,
+                Side::Default => net,
+            });
+        let initial_supply = (config.supply as i128 - net).clamp(0, u64::MAX as i128) as u64;
+
+        let mut daily: Vec<DailyRoyalties> = vec![];
+        let mut traders: HashMap<Pubkey, TraderRoyalties> = HashMap::new();
+
+        let mut trades: Vec<&TradeEvent> = trades.iter().filter(|t| t.id == config.id).collect();
+        trades.sort_by_key(|t| (t.slot, t.transaction_index));
+
+        let mut supply = initial_supply;
+        for trade in trades {
+            let quote = fees.quote(trade.side, supply, trade.amount);
+            supply = match trade.side {
+                Side::Buy => supply.saturating_add(trade.amount),
+                Side::Sell => supply.saturating_sub(trade.amount),
+                Side::Default => supply,
+            };
+            let Some(quote) = quote else {
+                continue;
+            };
+
+            let day = trade.block_time.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY;
+            if daily.last().map(|d| d.day) != Some(day) {
+                daily.push(DailyRoyalties {
+                    day,
+                    ..DailyRoyalties::default()
+                });
+            }
+            let entry = daily.last_mut().unwrap();
+            entry.royalties += quote.royalty;
+            entry.volume += quote.cost;
+            entry.trades += 1;
+
+            let trader = traders.entry(trade.trader).or_insert(TraderRoyalties {
+                trader: trade.trader,
+                royalties: 0,
+                trades: 0,
+            });
+            trader.royalties += quote.royalty;
+            trader.trades += 1;
+        }
+
+        let mut top_traders: Vec<TraderRoyalties> = traders.into_values().collect();
+        top_traders.sort_by(|a, b| b.royalties.cmp(&a.royalties).then(a.trader.cmp(&b.trader)));
+
+        let projections = supply_levels
+            .iter()
+            .map(|supply| RoyaltyProjection {
+                supply: *supply,
+                royalties: project_royalties(fees, config.supply, *supply),
+            })
+            .collect();
+
+        Self {
+            id: config.id,
+            owner: config.owner,
+            supply: config.supply,
+            royalties: config.royalties,
+            unclaimed: config.unclaimed,
+            claimed_royalties: config.royalties.saturating_sub(config.unclaimed),
+            debt: config.debt,
+            daily,
+            top_traders,
+            projections,
+        }
+    }
+}
+
+/// The royalties earned by buying from `supply` up to `target`, or selling down to it, `None` when
+/// the curve can't quote it.
+pub fn project_royalties(fees: &FeeSchedule, supply: u64, target: u64) -> Option<u64> {
+    let quote = if target >= supply {
+        fees.quote(Side::Buy, supply, target - supply)
+    } else {
+        fees.quote(Side::Sell, supply, supply - target)
+    };
+    quote.map(|quote| quote.royalty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DECIMAL_DENOMINATOR;
+    use anchor_lang::prelude::*;
+
+    fn trade(slot: u64, block_time: i64, trader: Pubkey, side: Side, keys: u64) -> TradeEvent {
+        TradeEvent {
+            slot,
+            transaction_index: 0,
+            block_time,
+            id: 42,
+            trader,
+            side,
+            amount: keys * DECIMAL_DENOMINATOR,
+        }
+    }
+
+    #[test]
+    fn test_creator_report() -> Result<()> {
+        let (whale, minnow) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let trades = vec![
+            trade(1, 100, whale, Side::Buy, 10),
+            trade(2, 200, minnow, Side::Buy, 1),
+            trade(3, SECONDS_PER_DAY + 1, minnow, Side::Sell, 1),
+        ];
+        let config = Config {
+            id: 42,
+            supply: 12 * DECIMAL_DENOMINATOR,
+            owner: Pubkey::new_unique(),
+            royalties: 1_000,
+            unclaimed: 400,
+            debt: 0,
+        };
+        let fees = FeeSchedule {
+            royalty_bps: 500,
+            protocol_bps: 500,
+        };
+
+        let report = CreatorReport::new(
+            &config,
+            &trades,
+            &fees,
+            &[12 * DECIMAL_DENOMINATOR, 20 * DECIMAL_DENOMINATOR],
+        );
+
+        assert_eq!(600, report.claimed_royalties);
+        assert_eq!(2, report.daily.len());
+        assert_eq!(0, report.daily[0].day);
+        assert_eq!(2, report.daily[0].trades);
+        assert_eq!(SECONDS_PER_DAY, report.daily[1].day);
+
+        // the history nets 10 keys so it starts at 2, the first buy is quoted from there
+        let first = fees
+            .quote(Side::Buy, 2 * DECIMAL_DENOMINATOR, 10 * DECIMAL_DENOMINATOR)
+            .unwrap();
+        assert_eq!(whale, report.top_traders[0].trader);
+        assert_eq!(first.royalty, report.top_traders[0].royalties);
+        assert_eq!(minnow, report.top_traders[1].trader);
+        assert_eq!(2, report.top_traders[1].trades);
+
+        let total: u64 = report.daily.iter().map(|d| d.royalties).sum();
+        let by_trader: u64 = report.top_traders.iter().map(|t| t.royalties).sum();
+        assert_eq!(total, by_trader);
+
+        assert_eq!(Some(0), report.projections[0].royalties);
+        assert_eq!(
+            fees.quote(Side::Buy, 12 * DECIMAL_DENOMINATOR, 8 * DECIMAL_DENOMINATOR)
+                .map(|quote| quote.royalty),
+            report.projections[1].royalties
+        );
+        assert_eq!(None, project_royalties(&fees, 0, u64::MAX));
+
+        Ok(())
+    }
+}
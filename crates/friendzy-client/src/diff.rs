@@ -0,0 +1,543 @@
+use anchor_lang::prelude::Pubkey;
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    calculate_buy_cost,
+    fees::{FeeSchedule, BPS_DENOMINATOR},
+    Config, Profile, Side,
+};
+
+/// The difference between the accrued and the expected royalties [`Config::diff`] tolerates, in
+/// basis points of the expected royalty and at least one lamport.
+///
+/// It covers the rounding of the program and of several trades between two snapshots, which each
+/// round their royalty down.
+pub const ROYALTY_TOLERANCE_BPS: u64 = 10;
+
+/// A change between two snapshots of a `Config`, amounts are in native units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigChange {
+    Buy {
+        amount: u64,
+    },
+    Sell {
+        amount: u64,
+    },
+    /// The royalties accrued by the trades.
+    RoyaltyAccrual {
+        amount: u64,
+    },
+    /// The unclaimed royalties claimed by the owner.
+    Withdrawal {
+        amount: u64,
+    },
+    DebtChange {
+        before: u64,
+        after: u64,
+    },
+    /// The owner set by a verify.
+    OwnerChange {
+        before: Pubkey,
+        after: Pubkey,
+    },
+}
+
+/// A change between two snapshots of a `Profile`, amounts are in native units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileChange {
+    Buy { amount: u64, volume: u64 },
+    Sell { amount: u64, volume: u64 },
+    ReservedChange { before: u64, after: u64 },
+}
+
+/// A state transition no known instruction produces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    IdChanged {
+        before: u64,
+        after: u64,
+    },
+    OwnerChanged {
+        before: Pubkey,
+        after: Pubkey,
+    },
+    /// A field which only ever grows went down.
+    Decreased {
+        field: &'static str,
+        before: u64,
+        after: u64,
+    },
+    /// The curve can't quote the supply change.
+    Unquotable {
+        side: Side,
+        supply: u64,
+        amount: u64,
+    },
+    /// The royalties accrued differ from the fee schedule applied to the curve by more than
+    /// [`ROYALTY_TOLERANCE_BPS`].
+    RoyaltyMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The royalties went up while the supply didn't move.
+    UnexplainedRoyalties {
+        amount: u64,
+    },
+    /// The unclaimed royalties went up by more than the royalties accrued.
+    UnexplainedUnclaimed {
+        accrued: u64,
+        unclaimed: u64,
+    },
+    UnclaimedAboveRoyalties {
+        unclaimed: u64,
+        royalties: u64,
+    },
+    /// The volume doesn't match the amount of keys traded.
+    VolumeMismatch {
+        side: Side,
+        amount: u64,
+        volume: u64,
+    },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inconsistency::IdChanged { before, after } => {
+                write!(f, "id changed from {} to {}", before, after)
+            }
+            Inconsistency::OwnerChanged { before, after } => {
+                write!(f, "owner changed from {} to {}", before, after)
+            }
+            Inconsistency::Decreased {
+                field,
+                before,
+                after,
+            } => write!(f, "{} decreased from {} to {}", field, before, after),
+            Inconsistency::Unquotable {
+                side,
+                supply,
+                amount,
+            } => write!(
+                f,
+                "{:?} of {} keys at supply {} can't be quoted",
+                side, amount, supply
+            ),
+            Inconsistency::RoyaltyMismatch { expected, actual } => {
+                write!(f, "royalties accrued {}, expected {}", actual, expected)
+            }
+            Inconsistency::UnexplainedRoyalties { amount } => {
+                write!(f, "royalties accrued {} without a trade", amount)
+            }
+            Inconsistency::UnexplainedUnclaimed { accrued, unclaimed } => write!(
+                f,
+                "unclaimed went up by {} with {} royalties accrued",
+                unclaimed, accrued
+            ),
+            Inconsistency::UnclaimedAboveRoyalties {
+                unclaimed,
+                royalties,
+            } => write!(f, "unclaimed {} above royalties {}", unclaimed, royalties),
+            Inconsistency::VolumeMismatch {
+                side,
+                amount,
+                volume,
+            } => write!(
+                f,
+                "{:?} of {} keys with a volume of {}",
+                side, amount, volume
+            ),
+        }
+    }
+}
+
+/// The classified changes between two snapshots of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff<C> {
+    pub changes: Vec<C>,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl<C> Default for Diff<C> {
+    fn default() -> Self {
+        Self {
+            changes: vec![],
+            inconsistencies: vec![],
+        }
+    }
+}
+
+impl<C> Diff<C> {
+    /// Whether the transition matches known instructions.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.inconsistencies.is_empty()
+    }
+}
+
+pub type ConfigDiff = Diff<ConfigChange>;
+pub type ProfileDiff = Diff<ProfileChange>;
+
+/// Whether the accrued royalties are within [`ROYALTY_TOLERANCE_BPS`] of the expected ones.
+fn within_tolerance(expected: u64, actual: u64) -> bool {
+    let tolerance =
+        (expected as u128 * ROYALTY_TOLERANCE_BPS as u128 / BPS_DENOMINATOR as u128).max(1);
+    (expected as i128 - actual as i128).unsigned_abs() <= tolerance
+}
+
+impl Config {
+    /// Classifies the changes from this snapshot to `after`.
+    ///
+    /// The royalties are checked against the fee schedule applied to the net supply change,
+    /// within [`ROYALTY_TOLERANCE_BPS`] for rounding. A buy and a sell of the same amount between
+    /// snapshots can't be seen.
+    pub fn diff(&self, after: &Config, fees: &FeeSchedule) -> ConfigDiff {
+        let mut diff = ConfigDiff::default();
+        if self.id != after.id {
+            diff.inconsistencies.push(Inconsistency::IdChanged {
+                before: self.id,
+                after: after.id,
+            });
+            return diff;
+        }
+
+        if self.owner != after.owner {
+            diff.changes.push(ConfigChange::OwnerChange {
+                before: self.owner,
+                after: after.owner,
+            });
+        }
+
+        let (side, amount) = match after.supply.cmp(&self.supply) {
+            Ordering::Greater => (Side::Buy, after.supply - self.supply),
+            Ordering::Less => (Side::Sell, self.supply - after.supply),
+            Ordering::Equal => (Side::Default, 0),
+        };
+        match side {
+            Side::Buy => diff.changes.push(ConfigChange::Buy { amount }),
+            Side::Sell => diff.changes.push(ConfigChange::Sell { amount }),
+            Side::Default => {}
+        }
+
+        if after.royalties < self.royalties {
+            diff.inconsistencies.push(Inconsistency::Decreased {
+                field: "royalties",
+                before: self.royalties,
+                after: after.royalties,
+            });
+        }
+        let accrued = after.royalties.saturating_sub(self.royalties);
+        if accrued > 0 {
+            diff.changes
+                .push(ConfigChange::RoyaltyAccrual { amount: accrued });
+        }
+
+        if side == Side::Default {
+            if accrued > 0 {
+                diff.inconsistencies
+                    .push(Inconsistency::UnexplainedRoyalties { amount: accrued });
+            }
+        } else {
+            match fees.quote(side, self.supply, amount) {
+                Some(quote) if !within_tolerance(quote.royalty, accrued) => {
+                    diff.inconsistencies.push(Inconsistency::RoyaltyMismatch {
+                        expected: quote.royalty,
+                        actual: accrued,
+                    })
+                }
+                Some(_) => {}
+                None => diff.inconsistencies.push(Inconsistency::Unquotable {
+                    side,
+                    supply: self.supply,
+                    amount,
+                }),
+            }
+        }
+
+        // the unclaimed royalties grow with the accrual and drop on a withdrawal
+        let available = self.unclaimed as u128 + accrued as u128;
+        match (after.unclaimed as u128).cmp(&available) {
+            Ordering::Less => diff.changes.push(ConfigChange::Withdrawal {
+                amount: (available - after.unclaimed as u128) as u64,
+            }),
+            Ordering::Greater => diff
+                .inconsistencies
+                .push(Inconsistency::UnexplainedUnclaimed {
+                    accrued,
+                    unclaimed: after.unclaimed.saturating_sub(self.unclaimed),
+                }),
+            Ordering::Equal => {}
+        }
+        if after.unclaimed > after.royalties {
+            diff.inconsistencies
+                .push(Inconsistency::UnclaimedAboveRoyalties {
+                    unclaimed: after.unclaimed,
+                    royalties: after.royalties,
+                });
+        }
+
+        if self.debt != after.debt {
+            diff.changes.push(ConfigChange::DebtChange {
+                before: self.debt,
+                after: after.debt,
+            });
+        }
+
+        diff
+    }
+}
+
+impl Profile {
+    /// Classifies the changes from this snapshot to `after`.
+    ///
+    /// The supply the trades happened at isn't known to a profile, so buy volumes are only checked
+    /// against the cheapest the keys can be bought for, at zero supply.
+    pub fn diff(&self, after: &Profile) -> ProfileDiff {
+        let mut diff = ProfileDiff::default();
+        if self.id != after.id {
+            diff.inconsistencies.push(Inconsistency::IdChanged {
+                before: self.id,
+                after: after.id,
+            });
+            return diff;
+        }
+        if self.owner != after.owner {
+            diff.inconsistencies.push(Inconsistency::OwnerChanged {
+                before: self.owner,
+                after: after.owner,
+            });
+        }
+
+        let sides = [
+            (
+                Side::Buy,
+                ("buy_amount", self.buy_amount, after.buy_amount),
+                ("buy_volume", self.buy_volume, after.buy_volume),
+            ),
+            (
+                Side::Sell,
+                ("sell_amount", self.sell_amount, after.sell_amount),
+                ("sell_volume", self.sell_volume, after.sell_volume),
+            ),
+        ];
+        for (side, amounts, volumes) in sides {
+            for (field, before, after) in [amounts, volumes] {
+                if after < before {
+                    diff.inconsistencies.push(Inconsistency::Decreased {
+                        field,
+                        before,
+                        after,
+                    });
+                }
+            }
+
+            let amount = amounts.2.saturating_sub(amounts.1);
+            let volume = volumes.2.saturating_sub(volumes.1);
+            if amount == 0 && volume == 0 {
+                continue;
+            }
+
+            // sell volumes may be net of fees, which aren't known here
+            let minimum = match side {
+                Side::Buy => calculate_buy_cost(0, amount).unwrap_or(u64::MAX),
+                _ => 1,
+            };
+            if amount == 0 || volume < minimum {
+                diff.inconsistencies.push(Inconsistency::VolumeMismatch {
+                    side,
+                    amount,
+                    volume,
+                });
+            }
+            diff.changes.push(match side {
+                Side::Sell => ProfileChange::Sell { amount, volume },
+                _ => ProfileChange::Buy { amount, volume },
+            });
+        }
+
+        if self.reserved != after.reserved {
+            diff.changes.push(ProfileChange::ReservedChange {
+                before: self.reserved,
+                after: after.reserved,
+            });
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DECIMAL_DENOMINATOR;
+    use anchor_lang::prelude::*;
+
+    fn config(supply: u64, royalties: u64, unclaimed: u64) -> Config {
+        Config {
+            id: 42,
+            supply,
+            owner: Pubkey::default(),
+            royalties,
+            unclaimed,
+            debt: 0,
+        }
+    }
+
+    #[test]
+    fn test_config_diff() -> Result<()> {
+        let fees = FeeSchedule {
+            royalty_bps: 500,
+            protocol_bps: 500,
+        };
+        let before = config(2 * DECIMAL_DENOMINATOR, 1_000, 400);
+
+        // a buy of 3 keys accruing the royalty of the curve cost
+        let royalty = fees
+            .quote(Side::Buy, 2 * DECIMAL_DENOMINATOR, 3 * DECIMAL_DENOMINATOR)
+            .unwrap()
+            .royalty;
+        let after = config(5 * DECIMAL_DENOMINATOR, 1_000 + royalty, 400 + royalty);
+        let diff = before.diff(&after, &fees);
+        assert!(diff.is_consistent());
+        assert_eq!(
+            vec![
+                ConfigChange::Buy {
+                    amount: 3 * DECIMAL_DENOMINATOR
+                },
+                ConfigChange::RoyaltyAccrual { amount: royalty },
+            ],
+            diff.changes
+        );
+
+        // the owner withdrawing with a verify and a debt change
+        let mut after = config(2 * DECIMAL_DENOMINATOR, 1_000, 0);
+        after.owner = Pubkey::new_unique();
+        after.debt = 7;
+        let diff = before.diff(&after, &fees);
+        assert!(diff.is_consistent());
+        assert_eq!(
+            vec![
+                ConfigChange::OwnerChange {
+                    before: before.owner,
+                    after: after.owner
+                },
+                ConfigChange::Withdrawal { amount: 400 },
+                ConfigChange::DebtChange {
+                    before: 0,
+                    after: 7
+                },
+            ],
+            diff.changes
+        );
+        assert!(before.diff(&before, &fees).is_empty());
+
+        // a sell accruing nothing and royalties without a trade
+        let after = config(DECIMAL_DENOMINATOR, 1_000, 400);
+        let diff = before.diff(&after, &fees);
+        assert_eq!(
+            vec![Inconsistency::RoyaltyMismatch {
+                expected: 508_333,
+                actual: 0
+            }],
+            diff.inconsistencies
+        );
+
+        // rounding is tolerated up to 10 bps of the expected royalty
+        for (accrued, consistent) in [
+            (508_333 - 508, true),
+            (508_333 + 508, true),
+            (508_333 - 509, false),
+            (508_333 + 509, false),
+        ] {
+            let after = config(DECIMAL_DENOMINATOR, 1_000 + accrued, 400 + accrued);
+            assert_eq!(consistent, before.diff(&after, &fees).is_consistent());
+        }
+        // and at least one lamport
+        assert!(within_tolerance(1, 2));
+        assert!(!within_tolerance(1, 3));
+
+        let diff = before.diff(&config(2 * DECIMAL_DENOMINATOR, 1_100, 600), &fees);
+        assert_eq!(
+            vec![
+                Inconsistency::UnexplainedRoyalties { amount: 100 },
+                Inconsistency::UnexplainedUnclaimed {
+                    accrued: 100,
+                    unclaimed: 200
+                },
+            ],
+            diff.inconsistencies
+        );
+        assert_eq!(
+            "royalties accrued 100 without a trade",
+            diff.inconsistencies[0].to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_diff() -> Result<()> {
+        let before = Profile {
+            id: 42,
+            buy_amount: DECIMAL_DENOMINATOR,
+            buy_volume: 10_000_000,
+            ..Profile::default()
+        };
+
+        let after = Profile {
+            buy_amount: 3 * DECIMAL_DENOMINATOR,
+            buy_volume: 40_000_000,
+            sell_amount: DECIMAL_DENOMINATOR,
+            sell_volume: 9_000_000,
+            ..before.clone()
+        };
+        let diff = before.diff(&after);
+        assert!(diff.is_consistent());
+        assert_eq!(
+            vec![
+                ProfileChange::Buy {
+                    amount: 2 * DECIMAL_DENOMINATOR,
+                    volume: 30_000_000
+                },
+                ProfileChange::Sell {
+                    amount: DECIMAL_DENOMINATOR,
+                    volume: 9_000_000
+                },
+            ],
+            diff.changes
+        );
+
+        // two keys can't be bought for less than 0.0201 SOL
+        let after = Profile {
+            buy_amount: 3 * DECIMAL_DENOMINATOR,
+            buy_volume: 20_000_000,
+            ..before.clone()
+        };
+        assert_eq!(
+            vec![Inconsistency::VolumeMismatch {
+                side: Side::Buy,
+                amount: 2 * DECIMAL_DENOMINATOR,
+                volume: 10_000_000
+            }],
+            before.diff(&after).inconsistencies
+        );
+
+        let after = Profile {
+            buy_amount: 0,
+            ..before.clone()
+        };
+        assert_eq!(
+            vec![Inconsistency::Decreased {
+                field: "buy_amount",
+                before: DECIMAL_DENOMINATOR,
+                after: 0
+            }],
+            before.diff(&after).inconsistencies
+        );
+
+        Ok(())
+    }
+}
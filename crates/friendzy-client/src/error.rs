@@ -0,0 +1,86 @@
+use solana_client::{client_error::ClientError as RpcError, pubsub_client::PubsubClientError};
+use solana_sdk::{
+    message::CompileError, pubkey::Pubkey, signer::SignerError, transaction::TransactionError,
+};
+use std::fmt;
+
+/// The errors returned by the client-side helpers enabled with the `client` feature.
+#[derive(Debug)]
+pub enum ClientError {
+    /// A transaction was built without a recent blockhash or durable nonce.
+    MissingBlockhash,
+    /// The instructions could not be compiled into a message.
+    Compile(CompileError),
+    /// The transaction could not be signed.
+    Signer(SignerError),
+    /// The RPC request failed.
+    Rpc(Box<RpcError>),
+    /// The websocket subscription failed.
+    Pubsub(Box<PubsubClientError>),
+    /// The simulated transaction failed.
+    Simulation(TransactionError),
+    /// The simulation did not report the compute units consumed.
+    MissingUnitsConsumed,
+    /// The batch leg at this index doesn't fit in a transaction on its own.
+    LegTooLarge(usize),
+    /// The curve can't quote the swap of this id, e.g. selling more than the supply.
+    Unquotable(u64),
+    /// Reading or writing local state failed.
+    Io(std::io::Error),
+    /// The account at this address exists but can't be decoded, e.g. after a layout change.
+    Undecodable(Pubkey),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::MissingBlockhash => write!(f, "missing recent blockhash or durable nonce"),
+            ClientError::Compile(e) => write!(f, "failed to compile message: {}", e),
+            ClientError::Signer(e) => write!(f, "failed to sign transaction: {}", e),
+            ClientError::Rpc(e) => write!(f, "rpc request failed: {}", e),
+            ClientError::Pubsub(e) => write!(f, "subscription failed: {}", e),
+            ClientError::Simulation(e) => write!(f, "simulation failed: {}", e),
+            ClientError::MissingUnitsConsumed => {
+                write!(f, "simulation did not report units consumed")
+            }
+            ClientError::Unquotable(id) => write!(f, "no quote for {}", id),
+            ClientError::Io(e) => write!(f, "io error: {}", e),
+            ClientError::Undecodable(address) => write!(f, "failed to decode account {}", address),
+            ClientError::LegTooLarge(index) => {
+                write!(f, "batch leg {} does not fit in a transaction", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<CompileError> for ClientError {
+    fn from(e: CompileError) -> Self {
+        ClientError::Compile(e)
+    }
+}
+
+impl From<SignerError> for ClientError {
+    fn from(e: SignerError) -> Self {
+        ClientError::Signer(e)
+    }
+}
+
+impl From<RpcError> for ClientError {
+    fn from(e: RpcError) -> Self {
+        ClientError::Rpc(Box::new(e))
+    }
+}
+
+impl From<PubsubClientError> for ClientError {
+    fn from(e: PubsubClientError) -> Self {
+        ClientError::Pubsub(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(e: std::io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
@@ -0,0 +1,361 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+};
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    program::FriendzyProgram,
+    ui,
+    validate::{swap_account_names, Mismatch, WITHDRAW_ACCOUNTS},
+    Side, SwapArgs, VerifyArgs, WithdrawArgs,
+};
+
+/// What a Friendzy instruction does, amounts are in native units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Buy {
+        amount: u64,
+        /// The most lamports the user pays, fees included.
+        max_price: u64,
+        first_purchase: bool,
+    },
+    Sell {
+        amount: u64,
+        /// The least lamports the user receives, after fees.
+        min_price: u64,
+    },
+    /// Claims the unclaimed royalties of the id.
+    Withdraw,
+    /// Sets the owner of the id.
+    Verify { owner: Pubkey },
+}
+
+/// An account of an instruction with the name of its position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedAccount {
+    pub name: &'static str,
+    pub pubkey: Pubkey,
+}
+
+/// A decoded Friendzy instruction, displayed as a sentence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub id: u64,
+    /// The X handle of the id, when known to the explainer.
+    pub handle: Option<String>,
+    /// The account at position 0 when it signs.
+    pub signer: Option<Pubkey>,
+    pub action: Action,
+    pub accounts: Vec<NamedAccount>,
+    /// The accounts which are not the ones derived from the id and signer.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let who = match &self.signer {
+            Some(signer) => format!("wallet {}", signer),
+            None => "an unsigned wallet".to_string(),
+        };
+        let keys = match &self.handle {
+            Some(handle) => format!("@{} (id {})", handle, self.id),
+            None => format!("id {}", self.id),
+        };
+
+        match &self.action {
+            Action::Buy {
+                amount,
+                max_price,
+                first_purchase,
+            } => {
+                write!(
+                    f,
+                    "{} bought {} keys of {} paying at most {} SOL",
+                    who,
+                    ui(*amount),
+                    keys,
+                    ui(*max_price)
+                )?;
+                if *first_purchase {
+                    write!(f, ", creating the mint")?;
+                }
+            }
+            Action::Sell { amount, min_price } => write!(
+                f,
+                "{} sold {} keys of {} receiving at least {} SOL",
+                who,
+                ui(*amount),
+                keys,
+                ui(*min_price)
+            )?,
+            Action::Withdraw => write!(f, "{} withdrew the royalties of {}", who, keys)?,
+            Action::Verify { owner } => write!(f, "{} verified {} for owner {}", who, keys, owner)?,
+        }
+
+        if !self.mismatches.is_empty() {
+            write!(f, " ({} account mismatches)", self.mismatches.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Explains Friendzy instructions, resolving ids to X handles when they are known.
+#[derive(Debug, Default, Clone)]
+pub struct Explainer {
+    program: FriendzyProgram,
+    handles: HashMap<u64, String>,
+}
+
+impl Explainer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_program(program: FriendzyProgram) -> Self {
+        Self {
+            program,
+            handles: HashMap::new(),
+        }
+    }
+
+    /// Adds the X handle of an id, without the `@`.
+    pub fn with_handle(mut self, id: u64, handle: &str) -> Self {
+        self.handles.insert(id, handle.to_string());
+        self
+    }
+
+    /// Explains the instruction, `None` if it's not a Friendzy instruction.
+    pub fn explain(&self, instruction: &Instruction) -> Option<Explanation> {
+        if instruction.program_id != self.program.program_id {
+            return None;
+        }
+
+        let (id, action, names, mismatches): (u64, Action, &[&'static str], Vec<Mismatch>) =
+            if let Ok(args) = SwapArgs::try_from_slice(&instruction.data) {
+                let first_purchase = instruction
+                    .accounts
+                    .get(8)
+                    .is_some_and(|meta| meta.pubkey != system_program::ID);
+                let action = match args.side {
+                    Side::Buy => Action::Buy {
+                        amount: args.amount,
+                        max_price: args.price,
+                        first_purchase,
+                    },
+                    Side::Sell => Action::Sell {
+                        amount: args.amount,
+                        min_price: args.price,
+                    },
+                    Side::Default => return None,
+                };
+                let names = swap_account_names(first_purchase);
+                let mismatches = self.program.validate_swap(instruction);
+                (args.id, action, names, mismatches)
+            } else if let Ok(args) = WithdrawArgs::try_from_slice(&instruction.data) {
+                let mismatches = self.program.validate_withdraw(instruction);
+                (
+                    args.id,
+                    Action::Withdraw,
+                    &WITHDRAW_ACCOUNTS[..],
+                    mismatches,
+                )
+            } else if let Ok(args) = VerifyArgs::try_from_slice(&instruction.data) {
+                let action = Action::Verify { owner: args.owner };
+                (args.id, action, &[][..], vec![])
+            } else {
+                return None;
+            };
+
+        let accounts = instruction
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(index, meta)| NamedAccount {
+                name: names.get(index).copied().unwrap_or("unknown"),
+                pubkey: meta.pubkey,
+            })
+            .collect();
+
+        Some(Explanation {
+            id,
+            handle: self.handles.get(&id).cloned(),
+            signer: instruction
+                .accounts
+                .first()
+                .filter(|meta| meta.is_signer)
+                .map(|meta| meta.pubkey),
+            action,
+            accounts,
+            mismatches,
+        })
+    }
+
+    /// Explains the Friendzy instructions of a transaction, in order.
+    ///
+    /// Instructions using accounts from address lookup tables are skipped, only the static
+    /// keys of the message are resolved.
+    #[cfg(feature = "client")]
+    pub fn explain_transaction(
+        &self,
+        transaction: &solana_sdk::transaction::VersionedTransaction,
+    ) -> Vec<Explanation> {
+        let message = &transaction.message;
+        let keys = message.static_account_keys();
+
+        message
+            .instructions()
+            .iter()
+            .filter_map(|compiled| {
+                let program_id = *keys.get(compiled.program_id_index as usize)?;
+                let accounts = compiled
+                    .accounts
+                    .iter()
+                    .map(|index| {
+                        let index = *index as usize;
+                        Some(AccountMeta {
+                            pubkey: *keys.get(index)?,
+                            is_signer: message.is_signer(index),
+                            is_writable: message.is_maybe_writable(index),
+                        })
+                    })
+                    .collect::<Option<Vec<AccountMeta>>>()?;
+
+                self.explain(&Instruction {
+                    program_id,
+                    accounts,
+                    data: compiled.data.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_verify_instruction_data, pda::*};
+    use spl_associated_token_account::get_associated_token_address;
+    use std::str::FromStr;
+
+    fn swap(user: &Pubkey, side: Side, amount: u64, price: u64) -> Instruction {
+        let id = 1_436_880_221_354_045_450;
+        let (bank, _) = derive_bank_address();
+        let (mint, _) = derive_mint_address(id);
+        let (config, _) = derive_config_address(id);
+        let (profile, _) = derive_profile_address(id, user);
+        let (metadata, _) = derive_metadata_address(&mint);
+        let token_account = get_associated_token_address(user, &mint);
+
+        crate::swap(
+            user,
+            &bank,
+            &config,
+            &mint,
+            &profile,
+            &metadata,
+            &token_account,
+            false,
+            id,
+            amount,
+            price,
+            side,
+        )
+    }
+
+    #[test]
+    fn test_explain_swap() -> Result<()> {
+        let user = Pubkey::from_str("Gf3sbc5Jb62jH7WcTr3WSNGDQLk1w6wcKMZXKK1SC1E6").unwrap();
+        let explainer = Explainer::new().with_handle(1_436_880_221_354_045_450, "stacc");
+
+        let explanation = explainer
+            .explain(&swap(&user, Side::Buy, 1_500_000_000, 478_333_334))
+            .unwrap();
+        assert_eq!(
+            "wallet Gf3sbc5Jb62jH7WcTr3WSNGDQLk1w6wcKMZXKK1SC1E6 bought 1.5 keys of @stacc \
+             (id 1436880221354045450) paying at most 0.478333334 SOL",
+            explanation.to_string()
+        );
+        assert_eq!("profile", explanation.accounts[4].name);
+        assert_eq!("placeholder", explanation.accounts[8].name);
+        assert!(explanation.mismatches.is_empty());
+
+        let mut instruction = swap(&user, Side::Sell, 2_000_000_000, 10_000_000);
+        instruction.accounts[4].pubkey = Pubkey::new_unique();
+        let explanation = Explainer::new().explain(&instruction).unwrap();
+        assert_eq!(
+            "wallet Gf3sbc5Jb62jH7WcTr3WSNGDQLk1w6wcKMZXKK1SC1E6 sold 2 keys of \
+             id 1436880221354045450 receiving at least 0.01 SOL (1 account mismatches)",
+            explanation.to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_other_instructions() -> Result<()> {
+        let owner = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+        let verify = Instruction {
+            program_id: crate::id(),
+            accounts: vec![],
+            data: create_verify_instruction_data(&owner, 42),
+        };
+        assert_eq!(
+            "an unsigned wallet verified id 42 for owner hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh",
+            Explainer::new().explain(&verify).unwrap().to_string()
+        );
+
+        let (bank, _) = derive_bank_address();
+        let (mint, _) = derive_mint_address(42);
+        let (config, _) = derive_config_address(42);
+        let (profile, _) = derive_profile_address(42, &owner);
+        let withdraw = crate::withdraw(&owner, &bank, &config, &mint, &profile, 42);
+        let explanation = Explainer::new().explain(&withdraw).unwrap();
+        assert_eq!(Action::Withdraw, explanation.action);
+        assert_eq!(Some(owner), explanation.signer);
+
+        let mut other = withdraw.clone();
+        other.program_id = Pubkey::new_unique();
+        assert_eq!(None, Explainer::new().explain(&other));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_explain_transaction() -> Result<()> {
+        use crate::TransactionBuilder;
+        use solana_sdk::hash::Hash;
+
+        let user = Pubkey::new_unique();
+        let transaction = TransactionBuilder::new(user)
+            .compute_unit_price(1)
+            .blockhash(Hash::new_unique())
+            .swap(
+                &FriendzyProgram::MAINNET,
+                42,
+                1_000_000_000,
+                20_000_000,
+                Side::Buy,
+                false,
+            )
+            .swap(
+                &FriendzyProgram::MAINNET,
+                7,
+                500_000_000,
+                0,
+                Side::Sell,
+                false,
+            )
+            .build_unsigned()
+            .unwrap();
+
+        let explanations = Explainer::new().explain_transaction(&transaction);
+        assert_eq!(2, explanations.len());
+        assert_eq!(42, explanations[0].id);
+        assert_eq!(Some(user), explanations[1].signer);
+        assert!(explanations.iter().all(|e| e.mismatches.is_empty()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{calculate_buy_cost, calculate_sell_proceeds, Side};
+
+/// The denominator for basis point fees.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// The fees charged on top of the curve on every swap, in basis points of the curve cost.
+///
+/// The fee rates have not been decoded from the program yet, so there is no default and callers
+/// pass the schedule they assume. friend.tech charges a 5% creator royalty and a 5% protocol fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSchedule {
+    pub royalty_bps: u64,
+    pub protocol_bps: u64,
+}
+
+/// The breakdown of a swap of `amount` keys at a given supply.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    pub side: Side,
+    pub amount: u64,
+    /// The lamports moved along the curve.
+    pub cost: u64,
+    /// The lamports accrued to the `Config` owner.
+    pub royalty: u64,
+    /// The lamports sent to the vault.
+    pub protocol_fee: u64,
+}
+
+impl Quote {
+    pub fn fees(&self) -> u64 {
+        self.royalty + self.protocol_fee
+    }
+
+    /// The lamports paid by the user for a buy, or received by the user for a sell.
+    pub fn total(&self) -> u64 {
+        match self.side {
+            Side::Sell => self.cost - self.fees(),
+            _ => self.cost + self.fees(),
+        }
+    }
+
+    /// Whether the quote respects the price bound of a swap, `max_price` for buys and
+    /// `min_price` for sells.
+    pub fn within_bound(&self, price: u64) -> bool {
+        match self.side {
+            Side::Sell => self.total() >= price,
+            _ => self.total() <= price,
+        }
+    }
+}
+
+impl FeeSchedule {
+    fn fee(cost: u64, bps: u64) -> u64 {
+        (cost as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Quotes a swap, returns `None` when selling more than the supply or on overflow.
+    pub fn quote(&self, side: Side, supply: u64, amount: u64) -> Option<Quote> {
+        let cost = match side {
+            Side::Buy => calculate_buy_cost(supply, amount)?,
+            Side::Sell => calculate_sell_proceeds(supply, amount)?,
+            Side::Default => return None,
+        };
+        let royalty = Self::fee(cost, self.royalty_bps);
+        let protocol_fee = Self::fee(cost, self.protocol_bps);
+
+        if side == Side::Sell && royalty + protocol_fee > cost {
+            return None;
+        }
+        if side == Side::Buy {
+            cost.checked_add(royalty)?.checked_add(protocol_fee)?;
+        }
+
+        Some(Quote {
+            side,
+            amount,
+            cost,
+            royalty,
+            protocol_fee,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+
+    #[test]
+    fn test_quote_buy() -> Result<()> {
+        let fees = FeeSchedule {
+            royalty_bps: 500,
+            protocol_bps: 500,
+        };
+        let quote = fees.quote(Side::Buy, 0, 1_000_000_000).unwrap();
+
+        assert_eq!(10_000_000, quote.cost);
+        assert_eq!(500_000, quote.royalty);
+        assert_eq!(500_000, quote.protocol_fee);
+        assert_eq!(11_000_000, quote.total());
+        assert!(quote.within_bound(11_000_000));
+        assert!(!quote.within_bound(10_999_999));
+        assert_eq!(None, fees.quote(Side::Buy, 0, u64::MAX));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_sell() -> Result<()> {
+        let fees = FeeSchedule {
+            royalty_bps: 250,
+            protocol_bps: 0,
+        };
+        let quote = fees
+            .quote(Side::Sell, 1_000_000_000, 1_000_000_000)
+            .unwrap();
+
+        assert_eq!(10_000_000, quote.cost);
+        assert_eq!(250_000, quote.royalty);
+        assert_eq!(9_750_000, quote.total());
+        assert!(quote.within_bound(9_750_000));
+        assert!(!quote.within_bound(9_750_001));
+
+        assert_eq!(None, fees.quote(Side::Sell, 0, 1_000_000_000));
+        assert_eq!(None, fees.quote(Side::Default, 0, 1_000_000_000));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    io::{self, Write},
+    num::NonZeroU64,
+};
+
+use crate::{backtest::TradeEvent, calculate_buy_cost, Side, DECIMAL_DENOMINATOR};
+
+/// The supply of an id after a trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupplyPoint {
+    pub slot: u64,
+    pub transaction_index: u32,
+    pub block_time: i64,
+    pub side: Side,
+    /// The amount of keys traded in native units.
+    pub amount: u64,
+    pub supply: u64,
+    /// The curve cost of the next whole key in lamports.
+    pub price: u64,
+}
+
+/// The curve cost of the next whole key, saturating at `u64::MAX` for supplies the curve
+/// can't price.
+pub(crate) fn spot_price(supply: u64) -> u64 {
+    calculate_buy_cost(supply, DECIMAL_DENOMINATOR).unwrap_or(u64::MAX)
+}
+
+/// Reconstructs the supply path of an id from its trades, starting at `initial_supply`.
+///
+/// Trades of other ids are ignored, the rest are applied in slot and transaction index order.
+pub fn supply_path(id: u64, initial_supply: u64, trades: &[TradeEvent]) -> Vec<SupplyPoint> {
+    let mut trades: Vec<&TradeEvent> = trades.iter().filter(|t| t.id == id).collect();
+    trades.sort_by_key(|t| (t.slot, t.transaction_index));
+
+    let mut supply = initial_supply;
+    trades
+        .into_iter()
+        .map(|trade| {
+            supply = match trade.side {
+                Side::Buy => supply.saturating_add(trade.amount),
+                Side::Sell => supply.saturating_sub(trade.amount),
+                Side::Default => supply,
+            };
+            SupplyPoint {
+                slot: trade.slot,
+                transaction_index: trade.transaction_index,
+                block_time: trade.block_time,
+                side: trade.side,
+                amount: trade.amount,
+                supply,
+                price: spot_price(supply),
+            }
+        })
+        .collect()
+}
+
+/// The prices of an interval in lamports, with the amount of keys traded in native units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candle {
+    /// The unix timestamp the interval starts at.
+    pub start: i64,
+    pub open: u64,
+    pub high: u64,
+    pub low: u64,
+    pub close: u64,
+    pub volume: u64,
+    pub trades: u32,
+}
+
+impl Candle {
+    pub const CSV_HEADER: &'static str = "start,open,high,low,close,volume,trades";
+}
+
+/// Groups a supply path into candles of `interval` seconds, intervals without trades are skipped.
+///
+/// The path must be sorted, as returned by [`supply_path`].
+pub fn candles(points: &[SupplyPoint], interval: NonZeroU64) -> Vec<Candle> {
+    let interval = i64::try_from(interval.get()).unwrap_or(i64::MAX);
+    let mut candles: Vec<Candle> = vec![];
+
+    for point in points {
+        let start = point.block_time.div_euclid(interval) * interval;
+        match candles.last_mut() {
+            Some(candle) if candle.start == start => {
+                candle.high = candle.high.max(point.price);
+                candle.low = candle.low.min(point.price);
+                candle.close = point.price;
+                candle.volume = candle.volume.saturating_add(point.amount);
+                candle.trades += 1;
+            }
+            _ => candles.push(Candle {
+                start,
+                open: point.price,
+                high: point.price,
+                low: point.price,
+                close: point.price,
+                volume: point.amount,
+                trades: 1,
+            }),
+        }
+    }
+
+    candles
+}
+
+/// Writes the candles as CSV with a header row.
+pub fn write_candles_csv<W: Write>(candles: &[Candle], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{}", Candle::CSV_HEADER)?;
+    for c in candles {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            c.start, c.open, c.high, c.low, c.close, c.volume, c.trades
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+
+    fn trade(
+        slot: u64,
+        transaction_index: u32,
+        block_time: i64,
+        side: Side,
+        keys: u64,
+    ) -> TradeEvent {
+        TradeEvent {
+            slot,
+            transaction_index,
+            block_time,
+            id: 42,
+            trader: Pubkey::default(),
+            side,
+            amount: keys * DECIMAL_DENOMINATOR,
+        }
+    }
+
+    #[test]
+    fn test_supply_path() -> Result<()> {
+        let mut other = trade(1, 0, 0, Side::Buy, 100);
+        other.id = 7;
+        let trades = vec![
+            trade(2, 0, 10, Side::Sell, 1),
+            other,
+            trade(1, 1, 5, Side::Buy, 2),
+            trade(1, 0, 5, Side::Buy, 3),
+        ];
+
+        let path = supply_path(42, 0, &trades);
+        let supplies: Vec<u64> = path
+            .iter()
+            .map(|p| p.supply / DECIMAL_DENOMINATOR)
+            .collect();
+        assert_eq!(vec![3, 5, 4], supplies);
+        assert_eq!(spot_price(4 * DECIMAL_DENOMINATOR), path[2].price);
+        assert_eq!(10_000_000, spot_price(0));
+        assert_eq!(u64::MAX, spot_price(u64::MAX - DECIMAL_DENOMINATOR));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_candles_csv() -> Result<()> {
+        let trades = vec![
+            trade(1, 0, 0, Side::Buy, 10),
+            trade(2, 0, 30, Side::Sell, 5),
+            trade(3, 0, 59, Side::Buy, 1),
+            trade(4, 0, 180, Side::Buy, 1),
+        ];
+        let path = supply_path(42, 0, &trades);
+        let candles = candles(&path, NonZeroU64::new(60).unwrap());
+
+        assert_eq!(2, candles.len());
+        assert_eq!(0, candles[0].start);
+        assert_eq!(path[0].price, candles[0].open);
+        assert_eq!(path[0].price, candles[0].high);
+        assert_eq!(path[1].price, candles[0].low);
+        assert_eq!(path[2].price, candles[0].close);
+        assert_eq!(16 * DECIMAL_DENOMINATOR, candles[0].volume);
+        assert_eq!(3, candles[0].trades);
+        assert_eq!(180, candles[1].start);
+        assert_eq!(
+            1,
+            super::candles(&path, NonZeroU64::new(u64::MAX).unwrap()).len()
+        );
+
+        let mut csv = vec![];
+        write_candles_csv(&candles, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(Candle::CSV_HEADER, lines[0]);
+        assert_eq!(
+            format!("180,{0},{0},{0},{0},1000000000,1", path[3].price),
+            lines[2]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use anchor_spl::token::spl_token::{self, state::Account as TokenAccount};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{error::ClientError, loader::BatchLoader, program::FriendzyProgram, Profile};
+
+/// The offset of the mint in a token account.
+const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+
+/// A wallet holding keys of an id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holder {
+    pub owner: Pubkey,
+    /// The keys held across every token account of the owner, in native units.
+    pub balance: u64,
+    pub token_accounts: Vec<Pubkey>,
+    /// The owner's `Profile` for the id, `None` if the keys were only ever transferred in.
+    pub profile: Option<Profile>,
+}
+
+/// Groups token accounts of a mint by owner, sorted by balance with empty accounts dropped.
+///
+/// Accounts which are not token accounts are ignored.
+pub fn group_token_accounts(accounts: &[(Pubkey, Vec<u8>)]) -> Vec<Holder> {
+    let mut holders: HashMap<Pubkey, Holder> = HashMap::new();
+
+    for (address, data) in accounts {
+        let Ok(account) = TokenAccount::unpack(data) else {
+            continue;
+        };
+        if account.amount == 0 {
+            continue;
+        }
+
+        let holder = holders.entry(account.owner).or_insert_with(|| Holder {
+            owner: account.owner,
+            balance: 0,
+            token_accounts: vec![],
+            profile: None,
+        });
+        holder.balance = holder.balance.saturating_add(account.amount);
+        holder.token_accounts.push(*address);
+    }
+
+    let mut holders: Vec<Holder> = holders.into_values().collect();
+    holders.sort_by(|a, b| b.balance.cmp(&a.balance).then(a.owner.cmp(&b.owner)));
+    holders
+}
+
+/// Returns every holder of the id with their `Profile`, sorted by balance.
+///
+/// This scans the token program with `getProgramAccounts`, which some RPC providers don't
+/// allow, see [`get_top_holders`] for those.
+pub fn get_holders(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    id: u64,
+) -> Result<Vec<Holder>, ClientError> {
+    let (mint, _) = program.derive_mint_address(id);
+    let accounts = rpc_client.get_program_accounts_with_config(
+        &spl_token::ID,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(TokenAccount::LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    TOKEN_ACCOUNT_MINT_OFFSET,
+                    mint.as_ref(),
+                )),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    let accounts: Vec<(Pubkey, Vec<u8>)> = accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect();
+    let mut holders = group_token_accounts(&accounts);
+    attach_profiles(rpc_client, program, id, &mut holders)?;
+
+    Ok(holders)
+}
+
+/// Returns the holders of the 20 largest token accounts of the id with their `Profile`, using
+/// `getTokenLargestAccounts`.
+pub fn get_top_holders(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    id: u64,
+) -> Result<Vec<Holder>, ClientError> {
+    let (mint, _) = program.derive_mint_address(id);
+    let addresses: Vec<Pubkey> = rpc_client
+        .get_token_largest_accounts(&mint)?
+        .iter()
+        .filter_map(|account| Pubkey::from_str(&account.address).ok())
+        .collect();
+
+    let fetched = BatchLoader::new(rpc_client)
+        .with_program(*program)
+        .get_multiple_accounts(&addresses)?;
+    let accounts: Vec<(Pubkey, Vec<u8>)> = addresses
+        .iter()
+        .zip(fetched)
+        .filter_map(|(address, account)| Some((*address, account?.data)))
+        .collect();
+    let mut holders = group_token_accounts(&accounts);
+    attach_profiles(rpc_client, program, id, &mut holders)?;
+
+    Ok(holders)
+}
+
+/// Fetches the `Profile` of every holder.
+fn attach_profiles(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    id: u64,
+    holders: &mut [Holder],
+) -> Result<(), ClientError> {
+    let profiles: Vec<(u64, Pubkey)> = holders.iter().map(|holder| (id, holder.owner)).collect();
+    let profiles = BatchLoader::new(rpc_client)
+        .with_program(*program)
+        .get_profiles(&profiles)?;
+
+    for (holder, profile) in holders.iter_mut().zip(profiles) {
+        holder.profile = profile;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::program_option::COption;
+    use spl_token::state::AccountState;
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        let account = TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(account, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_group_token_accounts() {
+        let (mint, _) = FriendzyProgram::MAINNET.derive_mint_address(42);
+        let whale = Pubkey::new_unique();
+        let minnow = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let (a, b, c, d) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let holders = group_token_accounts(&[
+            (a, token_account(&mint, &minnow, 1_000_000_000)),
+            (b, token_account(&mint, &whale, 3_000_000_000)),
+            (c, token_account(&mint, &whale, 2_000_000_000)),
+            (d, token_account(&mint, &seller, 0)),
+            (Pubkey::new_unique(), vec![1, 2, 3]),
+        ]);
+
+        assert_eq!(2, holders.len());
+        assert_eq!(whale, holders[0].owner);
+        assert_eq!(5_000_000_000, holders[0].balance);
+        assert_eq!(vec![b, c], holders[0].token_accounts);
+        assert_eq!(minnow, holders[1].owner);
+        assert_eq!(None, holders[1].profile);
+    }
+}
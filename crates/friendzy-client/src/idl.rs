@@ -0,0 +1,514 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{SwapArgs, VerifyArgs, WithdrawArgs, VERIFY_DISCRIMINATOR, WITHDRAW_DISCRIMINATOR};
+
+/// The IDL shipped with the crate, regenerate it with `cargo run --example idl --features serde`.
+pub const IDL_JSON: &str = include_str!("../idl/friendzy.json");
+
+/// An Anchor style IDL of the Friendzy program.
+///
+/// The program is not an Anchor program, instructions are told apart by the byte described by
+/// [`IdlDiscriminant`] and accounts have no discriminator. The `discriminant` of instructions and
+/// the `optional` of fields are extensions Anchor tooling doesn't know, see `idl/README.md`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Idl {
+    pub version: String,
+    pub name: String,
+    pub instructions: Vec<IdlInstruction>,
+    pub accounts: Vec<IdlTypeDefinition>,
+    pub types: Vec<IdlTypeDefinition>,
+    pub metadata: IdlMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlMetadata {
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
+    pub discriminant: IdlDiscriminant,
+    pub accounts: Vec<IdlAccount>,
+    /// Every byte of the instruction data in order, the discriminant included.
+    pub args: Vec<IdlField>,
+}
+
+/// The byte of the instruction data telling the instructions apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlDiscriminant {
+    pub offset: usize,
+    pub values: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlAccount {
+    pub name: String,
+    pub is_mut: bool,
+    pub is_signer: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+    /// A trailing field which may be missing from the data.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IdlType {
+    /// `u8`, `u64` or `publicKey`.
+    Primitive(String),
+    Defined {
+        defined: String,
+    },
+}
+
+impl IdlType {
+    /// The serialized size, `None` for unknown types.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            IdlType::Primitive(ty) => match ty.as_str() {
+                "u8" => Some(1),
+                "u64" => Some(8),
+                "publicKey" => Some(32),
+                _ => None,
+            },
+            IdlType::Defined { defined } => match defined.as_str() {
+                "Side" => Some(1),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlTypeDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefinitionTy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlTypeDefinitionTy {
+    Struct { fields: Vec<IdlField> },
+    Enum { variants: Vec<IdlEnumVariant> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlEnumVariant {
+    pub name: String,
+}
+
+fn field(name: &str, ty: &str, docs: &[&str]) -> IdlField {
+    IdlField {
+        name: name.to_string(),
+        docs: docs.iter().map(|doc| doc.to_string()).collect(),
+        ty: IdlType::Primitive(ty.to_string()),
+        optional: false,
+    }
+}
+
+fn account(name: &str, is_mut: bool, is_signer: bool, docs: &[&str]) -> IdlAccount {
+    IdlAccount {
+        name: name.to_string(),
+        is_mut,
+        is_signer,
+        docs: docs.iter().map(|doc| doc.to_string()).collect(),
+    }
+}
+
+fn struct_definition(name: &str, docs: &[&str], fields: Vec<IdlField>) -> IdlTypeDefinition {
+    IdlTypeDefinition {
+        name: name.to_string(),
+        docs: docs.iter().map(|doc| doc.to_string()).collect(),
+        ty: IdlTypeDefinitionTy::Struct { fields },
+    }
+}
+
+/// The accounts shared by swap and withdraw at positions 0 to 7.
+fn common_accounts() -> Vec<IdlAccount> {
+    vec![
+        account("user", true, true, &[]),
+        account("bank", true, false, &[]),
+        account("config", true, false, &[]),
+        account("tokenMint", true, false, &[]),
+        account("profile", true, false, &[]),
+        account("tokenProgram", false, false, &[]),
+        account("rent", false, false, &[]),
+        account("systemProgram", false, false, &[]),
+    ]
+}
+
+/// Builds the IDL of the Friendzy program as deployed on mainnet.
+pub fn idl() -> Idl {
+    let placeholder = "The system program unless it's a first purchase.";
+    let mut swap_accounts = common_accounts();
+    swap_accounts.extend([
+        account("metadata", true, false, &[placeholder]),
+        account("metaplexProgram", false, false, &[placeholder]),
+        account("tokenAccount", true, false, &[]),
+        account("vault", true, false, &[]),
+        account("associatedTokenProgram", false, false, &[]),
+    ]);
+
+    let mut withdraw_accounts = common_accounts();
+    withdraw_accounts.extend((8..11).map(|index| {
+        account(
+            &format!("placeholder{}", index),
+            false,
+            false,
+            &["The system program."],
+        )
+    }));
+
+    let version = field("version", "u8", &["Only version 0 is known."]);
+    let id = field("id", "u64", &["The X user id."]);
+
+    Idl {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        name: "friendzy".to_string(),
+        instructions: vec![
+            IdlInstruction {
+                name: "verify".to_string(),
+                docs: vec![
+                    "Sets the owner of an id.".to_string(),
+                    "The accounts are the mock program's, no mainnet verify has been recorded."
+                        .to_string(),
+                ],
+                discriminant: IdlDiscriminant {
+                    offset: VerifyArgs::PADDING_INDEX,
+                    values: vec![VERIFY_DISCRIMINATOR],
+                },
+                accounts: vec![
+                    account("authority", true, true, &[]),
+                    account("config", true, false, &[]),
+                    account("systemProgram", false, false, &[]),
+                ],
+                args: vec![
+                    version.clone(),
+                    id.clone(),
+                    field("instruction", "u8", &[]),
+                    field("owner", "publicKey", &[]),
+                ],
+            },
+            IdlInstruction {
+                name: "swap".to_string(),
+                docs: vec!["Buys or sells keys of an id.".to_string()],
+                discriminant: IdlDiscriminant {
+                    offset: SwapArgs::SIDE_INDEX,
+                    values: vec![1, 2],
+                },
+                accounts: swap_accounts,
+                args: vec![
+                    version.clone(),
+                    id.clone(),
+                    IdlField {
+                        name: "side".to_string(),
+                        docs: vec![],
+                        ty: IdlType::Defined {
+                            defined: "Side".to_string(),
+                        },
+                        optional: false,
+                    },
+                    field("amount", "u64", &["The keys in native units."]),
+                    field(
+                        "price",
+                        "u64",
+                        &["The max lamports paid on a buy, the min lamports received on a sell."],
+                    ),
+                    IdlField {
+                        optional: true,
+                        ..field(
+                            "extra",
+                            "u64",
+                            &["Unknown meaning, unverified, no recorded transaction carries it."],
+                        )
+                    },
+                ],
+            },
+            IdlInstruction {
+                name: "withdraw".to_string(),
+                docs: vec!["Claims the unclaimed royalties of an id.".to_string()],
+                discriminant: IdlDiscriminant {
+                    offset: WithdrawArgs::PADDING_INDEX,
+                    values: vec![WITHDRAW_DISCRIMINATOR],
+                },
+                accounts: withdraw_accounts,
+                args: vec![version, id, field("instruction", "u8", &[])],
+            },
+        ],
+        accounts: vec![
+            struct_definition(
+                "Config",
+                &["The state of an id, at the config PDA."],
+                vec![
+                    field("id", "u64", &[]),
+                    field("supply", "u64", &[]),
+                    field("owner", "publicKey", &[]),
+                    field("royalties", "u64", &[]),
+                    field("unclaimed", "u64", &[]),
+                    field("debt", "u64", &[]),
+                ],
+            ),
+            struct_definition(
+                "Profile",
+                &["The trades of a wallet on an id, at the profile PDA."],
+                vec![
+                    field("id", "u64", &[]),
+                    field("owner", "publicKey", &[]),
+                    field("buyAmount", "u64", &[]),
+                    field("sellAmount", "u64", &[]),
+                    field("buyVolume", "u64", &[]),
+                    field("sellVolume", "u64", &[]),
+                    field("reserved", "u64", &[]),
+                ],
+            ),
+        ],
+        types: vec![IdlTypeDefinition {
+            name: "Side".to_string(),
+            docs: vec![],
+            ty: IdlTypeDefinitionTy::Enum {
+                variants: ["Default", "Buy", "Sell"]
+                    .iter()
+                    .map(|name| IdlEnumVariant {
+                        name: name.to_string(),
+                    })
+                    .collect(),
+            },
+        }],
+        metadata: IdlMetadata {
+            address: crate::ID.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pda::*,
+        validate::{swap_account_names, WITHDRAW_ACCOUNTS},
+        Config, Profile, Side,
+    };
+    use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+
+    /// Converts the names of `validate` to camel case.
+    fn camel(name: &str) -> String {
+        let mut words = name.split(' ');
+        let mut camel = words.next().unwrap_or_default().to_string();
+        for word in words {
+            camel.push_str(&word[..1].to_uppercase());
+            camel.push_str(&word[1..]);
+        }
+        camel
+    }
+
+    /// The offset of every field with the total size.
+    fn offsets(fields: &[IdlField]) -> (Vec<(String, usize)>, usize) {
+        let mut offset = 0;
+        let offsets = fields
+            .iter()
+            .map(|field| {
+                let start = offset;
+                offset += field.ty.size().unwrap();
+                (field.name.clone(), start)
+            })
+            .collect();
+        (offsets, offset)
+    }
+
+    fn instruction<'a>(idl: &'a Idl, name: &str) -> &'a IdlInstruction {
+        idl.instructions.iter().find(|ix| ix.name == name).unwrap()
+    }
+
+    fn struct_fields<'a>(idl: &'a Idl, name: &str) -> &'a [IdlField] {
+        let account = idl.accounts.iter().find(|a| a.name == name).unwrap();
+        let IdlTypeDefinitionTy::Struct { fields } = &account.ty else {
+            panic!("{} is not a struct", name);
+        };
+        fields
+    }
+
+    fn check_accounts(idl: &IdlInstruction, instruction: &Instruction, names: &[&str]) {
+        assert_eq!(instruction.accounts.len(), idl.accounts.len());
+        for (index, (account, meta)) in idl.accounts.iter().zip(&instruction.accounts).enumerate() {
+            assert_eq!(meta.is_writable, account.is_mut, "{}", account.name);
+            assert_eq!(meta.is_signer, account.is_signer, "{}", account.name);
+            if names[index] != "placeholder" {
+                assert_eq!(camel(names[index]), account.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_idl_is_shipped() -> Result<()> {
+        let shipped: Idl = serde_json::from_str(IDL_JSON).unwrap();
+        assert_eq!(
+            idl(),
+            shipped,
+            "idl/friendzy.json is stale, run `cargo run --example idl --features serde`"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_idl_matches_types() -> Result<()> {
+        let idl = idl();
+        let user = Pubkey::new_unique();
+        let (mint, _) = derive_mint_address(42);
+
+        // the accounts match the builders
+        let swap = crate::swap(
+            &user,
+            &derive_bank_address().0,
+            &derive_config_address(42).0,
+            &mint,
+            &derive_profile_address(42, &user).0,
+            &derive_metadata_address(&mint).0,
+            &Pubkey::new_unique(),
+            true,
+            42,
+            1,
+            1,
+            Side::Buy,
+        );
+        check_accounts(instruction(&idl, "swap"), &swap, swap_account_names(true));
+        let withdraw = crate::withdraw(
+            &user,
+            &derive_bank_address().0,
+            &derive_config_address(42).0,
+            &mint,
+            &derive_profile_address(42, &user).0,
+            42,
+        );
+        check_accounts(instruction(&idl, "withdraw"), &withdraw, &WITHDRAW_ACCOUNTS);
+
+        // the args match the data layouts
+        let (args, len) = offsets(&instruction(&idl, "swap").args);
+        assert_eq!(SwapArgs::EXTENDED_LEN, len);
+        assert_eq!(
+            vec![
+                ("version".to_string(), SwapArgs::VERSION_INDEX),
+                ("id".to_string(), SwapArgs::ID_INDEX),
+                ("side".to_string(), SwapArgs::SIDE_INDEX),
+                ("amount".to_string(), SwapArgs::AMOUNT_INDEX),
+                ("price".to_string(), SwapArgs::PRICE_INDEX),
+                ("extra".to_string(), SwapArgs::EXTRA_INDEX),
+            ],
+            args
+        );
+        let (args, len) = offsets(&instruction(&idl, "verify").args);
+        assert_eq!(VerifyArgs::LEN, len);
+        assert_eq!(("owner".to_string(), VerifyArgs::OWNER_INDEX), args[3]);
+        let (_, len) = offsets(&instruction(&idl, "withdraw").args);
+        assert_eq!(WithdrawArgs::LEN, len);
+
+        // the discriminants point at the byte the encoders write
+        for (name, data) in [
+            ("verify", crate::create_verify_instruction_data(&user, 42)),
+            (
+                "swap",
+                crate::create_swap_instruction_data(42, 1, 1, Side::Sell),
+            ),
+            ("withdraw", crate::create_withdraw_instruction_data(42)),
+        ] {
+            let discriminant = &instruction(&idl, name).discriminant;
+            assert!(
+                discriminant.values.contains(&data[discriminant.offset]),
+                "{}",
+                name
+            );
+        }
+
+        let IdlTypeDefinitionTy::Enum { variants } = &idl.types[0].ty else {
+            panic!("Side is not an enum");
+        };
+        for (index, side) in [Side::Default, Side::Buy, Side::Sell].iter().enumerate() {
+            assert_eq!(format!("{:?}", side), variants[index].name);
+            assert_eq!(vec![index as u8], side.try_to_vec().unwrap());
+        }
+
+        // the account layouts match the borsh encoding
+        let owner = Pubkey::new_unique();
+        let config = Config {
+            id: 1,
+            supply: 2,
+            owner,
+            royalties: 3,
+            unclaimed: 4,
+            debt: 5,
+        }
+        .try_to_vec()
+        .unwrap();
+        let (fields, len) = offsets(struct_fields(&idl, "Config"));
+        assert_eq!(Config::LEN, len);
+        for (name, offset) in fields {
+            let expected = match name.as_str() {
+                "id" => 1u64.to_le_bytes().to_vec(),
+                "supply" => 2u64.to_le_bytes().to_vec(),
+                "owner" => owner.to_bytes().to_vec(),
+                "royalties" => 3u64.to_le_bytes().to_vec(),
+                "unclaimed" => 4u64.to_le_bytes().to_vec(),
+                "debt" => 5u64.to_le_bytes().to_vec(),
+                name => panic!("unknown Config field {}", name),
+            };
+            assert_eq!(
+                expected,
+                config[offset..offset + expected.len()],
+                "{}",
+                name
+            );
+        }
+
+        let profile = Profile {
+            id: 1,
+            owner,
+            buy_amount: 2,
+            sell_amount: 3,
+            buy_volume: 4,
+            sell_volume: 5,
+            reserved: 6,
+        }
+        .try_to_vec()
+        .unwrap();
+        let (fields, len) = offsets(struct_fields(&idl, "Profile"));
+        assert_eq!(Profile::LEN, len);
+        for (name, offset) in fields {
+            let expected = match name.as_str() {
+                "id" => 1u64.to_le_bytes().to_vec(),
+                "owner" => owner.to_bytes().to_vec(),
+                "buyAmount" => 2u64.to_le_bytes().to_vec(),
+                "sellAmount" => 3u64.to_le_bytes().to_vec(),
+                "buyVolume" => 4u64.to_le_bytes().to_vec(),
+                "sellVolume" => 5u64.to_le_bytes().to_vec(),
+                "reserved" => 6u64.to_le_bytes().to_vec(),
+                name => panic!("unknown Profile field {}", name),
+            };
+            assert_eq!(
+                expected,
+                profile[offset..offset + expected.len()],
+                "{}",
+                name
+            );
+        }
+
+        Ok(())
+    }
+}
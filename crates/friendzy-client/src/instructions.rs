@@ -3,9 +3,39 @@ use anchor_lang::{
     solana_program::{instruction::Instruction, system_program, sysvar::SysvarId},
 };
 use anchor_spl::{associated_token, token::spl_token};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::program::FriendzyProgram;
+
+/// The instruction version built by default.
+pub const VERSION: u8 = 0;
+
+/// The instruction versions the decoders understand.
+pub const SUPPORTED_VERSIONS: [u8; 1] = [VERSION];
+
+/// The byte at [`VerifyArgs::PADDING_INDEX`] telling the instructions apart.
+pub const VERIFY_DISCRIMINATOR: u8 = 0;
+pub const WITHDRAW_DISCRIMINATOR: u8 = 3;
+
+#[error_code]
+pub enum InstructionError {
+    #[msg("Unknown instruction version")]
+    UnknownVersion,
+}
+
+/// Reads the version byte, failing on versions not in [`SUPPORTED_VERSIONS`].
+fn read_version(data: &[u8]) -> Result<u8> {
+    let version = *data.first().ok_or(ProgramError::InvalidInstructionData)?;
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        return Err(error!(InstructionError::UnknownVersion)
+            .with_values((version, SUPPORTED_VERSIONS[SUPPORTED_VERSIONS.len() - 1])));
+    }
+    Ok(version)
+}
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct VerifyArgs {
+    pub version: u8,
     pub id: u64,
     pub owner: Pubkey,
 }
@@ -22,15 +52,31 @@ impl VerifyArgs {
             return Err(ProgramError::InvalidInstructionData.into());
         }
 
+        let version = read_version(data)?;
         let id = u64::try_from_slice(&data[Self::ID_INDEX..Self::PADDING_INDEX]).unwrap();
         let owner = Pubkey::try_from_slice(&data[Self::OWNER_INDEX..]).unwrap();
 
-        Ok(Self { id, owner })
+        Ok(Self { version, id, owner })
+    }
+
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        // 0 - version
+        // 1 - twitter/x user_id
+        // 9 - instruction
+        // 10 - owner
+        [
+            vec![self.version],
+            self.id.try_to_vec().unwrap_or_default(),
+            vec![VERIFY_DISCRIMINATOR],
+            self.owner.try_to_vec().unwrap_or_default(),
+        ]
+        .concat()
     }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct WithdrawArgs {
+    pub version: u8,
     pub id: u64,
 }
 
@@ -44,47 +90,142 @@ impl WithdrawArgs {
         if data.len() != Self::LEN {
             return Err(ProgramError::InvalidInstructionData.into());
         }
+        let version = read_version(data)?;
         let id = u64::try_from_slice(&data[Self::ID_INDEX..Self::PADDING_INDEX]).unwrap();
 
-        Ok(Self { id })
+        Ok(Self { version, id })
+    }
+
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        // 0 - version
+        // 1 - twitter/x user_id that is commonly found in the API responses
+        // 9 - instruction
+        [
+            vec![self.version],
+            self.id.try_to_vec().unwrap_or_default(),
+            vec![WITHDRAW_DISCRIMINATOR],
+        ]
+        .concat()
     }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct SwapArgs {
+    pub version: u8,
     pub id: u64,
     pub side: Side,
     pub amount: u64,
     pub price: u64,
+    /// The trailing number of the extended layout, its meaning is unknown.
+    ///
+    /// The extended layout is unverified, no recorded transaction carries it yet.
+    pub extra: Option<u64>,
 }
 
 impl SwapArgs {
     pub const LEN: usize = 26;
+    /// The length of the layout with the trailing number, unverified against recorded data.
+    pub const EXTENDED_LEN: usize = 34;
     pub const VERSION_INDEX: usize = 0;
     pub const ID_INDEX: usize = 1;
     pub const SIDE_INDEX: usize = 9;
     pub const AMOUNT_INDEX: usize = 10;
     pub const PRICE_INDEX: usize = 18;
+    pub const EXTRA_INDEX: usize = 26;
 
     pub fn try_from_slice(data: &[u8]) -> Result<Self> {
-        if data.len() != Self::LEN {
+        if data.len() != Self::LEN && data.len() != Self::EXTENDED_LEN {
             return Err(ProgramError::InvalidInstructionData.into());
         }
+        let version = read_version(data)?;
         let id = u64::try_from_slice(&data[Self::ID_INDEX..Self::SIDE_INDEX]).unwrap();
-        let side = Side::try_from_slice(&data[Self::SIDE_INDEX..Self::AMOUNT_INDEX]).unwrap();
+        let side = Side::try_from_slice(&data[Self::SIDE_INDEX..Self::AMOUNT_INDEX])?;
         let amount = u64::try_from_slice(&data[Self::AMOUNT_INDEX..Self::PRICE_INDEX]).unwrap();
-        let price = u64::try_from_slice(&data[Self::PRICE_INDEX..]).unwrap();
+        let price = u64::try_from_slice(&data[Self::PRICE_INDEX..Self::EXTRA_INDEX]).unwrap();
+        let extra = match data.len() {
+            Self::EXTENDED_LEN => Some(u64::try_from_slice(&data[Self::EXTRA_INDEX..]).unwrap()),
+            _ => None,
+        };
 
         Ok(Self {
+            version,
             id,
             side,
             amount,
             price,
+            extra,
         })
     }
+
+    /// Encodes the args, with the trailing number when `extra` is set.
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        // 0 - version
+        // 1 - twitter/x user_id that is commonly found in the API responses
+        // 9 - the side, either `Default` = 0 | `Buy` = 1 | `Sell` = 2 with u8 alignment or sell
+        // 10 - the amount of keys to buy, this is denominated in native units
+        // 18 - the price, !!!!! IMPORTANT !!!!! this will be `max_price` for Side == Side::Buy otherwise
+        // 26 - there appears to be another u64 at the end of some instructions, its meaning is
+        //      unknown, only written when `extra` is set
+        [
+            vec![self.version],
+            self.id.try_to_vec().unwrap_or_default(),
+            self.side.try_to_vec().unwrap_or_default(),
+            self.amount.try_to_vec().unwrap_or_default(),
+            self.price.try_to_vec().unwrap_or_default(),
+            self.extra
+                .map(|extra| extra.to_le_bytes().to_vec())
+                .unwrap_or_default(),
+        ]
+        .concat()
+    }
+}
+
+/// A decoded Friendzy instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FriendzyInstruction {
+    Verify(VerifyArgs),
+    Swap(SwapArgs),
+    Withdraw(WithdrawArgs),
+}
+
+impl FriendzyInstruction {
+    /// Decodes the instruction from the discriminator at [`SwapArgs::SIDE_INDEX`].
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        match data.get(SwapArgs::SIDE_INDEX) {
+            Some(&VERIFY_DISCRIMINATOR) => VerifyArgs::try_from_slice(data).map(Self::Verify),
+            Some(1) | Some(2) => SwapArgs::try_from_slice(data).map(Self::Swap),
+            Some(&WITHDRAW_DISCRIMINATOR) => WithdrawArgs::try_from_slice(data).map(Self::Withdraw),
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            Self::Verify(args) => args.version,
+            Self::Swap(args) => args.version,
+            Self::Withdraw(args) => args.version,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Verify(args) => args.id,
+            Self::Swap(args) => args.id,
+            Self::Withdraw(args) => args.id,
+        }
+    }
+
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        match self {
+            Self::Verify(args) => args.to_instruction_data(),
+            Self::Swap(args) => args.to_instruction_data(),
+            Self::Withdraw(args) => args.to_instruction_data(),
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Side {
     #[default]
@@ -93,6 +234,167 @@ pub enum Side {
     Sell = 2,
 }
 
+/// The accounts of a swap, [`FriendzyProgram::swap_accounts`] derives them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapAccounts {
+    pub user: Pubkey,
+    pub bank: Pubkey,
+    pub config: Pubkey,
+    pub token_mint: Pubkey,
+    pub profile: Pubkey,
+    /// Only passed on a first purchase.
+    pub metadata: Pubkey,
+    pub token_account: Pubkey,
+}
+
+impl FriendzyProgram {
+    /// Derives the swap accounts of the user for the id, with the user's associated token account.
+    pub fn swap_accounts(&self, id: u64, user: &Pubkey) -> SwapAccounts {
+        let (bank, _) = self.derive_bank_address();
+        let (token_mint, _) = self.derive_mint_address(id);
+        let (config, _) = self.derive_config_address(id);
+        let (profile, _) = self.derive_profile_address(id, user);
+        let (metadata, _) = self.derive_metadata_address(&token_mint);
+        SwapAccounts {
+            user: *user,
+            bank,
+            config,
+            token_mint,
+            profile,
+            metadata,
+            token_account: get_associated_token_address(user, &token_mint),
+        }
+    }
+
+    pub fn swap(
+        &self,
+        accounts: &SwapAccounts,
+        first_purchase: bool,
+        id: u64,
+        amount: u64,
+        price: u64,
+        side: Side,
+    ) -> Instruction {
+        self.swap_with_args(
+            accounts,
+            first_purchase,
+            &SwapArgs {
+                version: VERSION,
+                id,
+                side,
+                amount,
+                price,
+                extra: None,
+            },
+        )
+    }
+
+    /// A swap encoding the args as given, e.g. to choose the version or the trailing number.
+    pub fn swap_with_args(
+        &self,
+        accounts: &SwapAccounts,
+        first_purchase: bool,
+        args: &SwapArgs,
+    ) -> Instruction {
+        let SwapAccounts {
+            user,
+            bank,
+            config,
+            token_mint,
+            profile,
+            metadata,
+            token_account,
+        } = accounts;
+        let mut accounts = vec![
+            AccountMeta::new(*user, true),                        // 0 - user
+            AccountMeta::new(*bank, false),                       // 1 - bank
+            AccountMeta::new(*config, false),                     // 2 - config
+            AccountMeta::new(*token_mint, false),                 // 3 - token mint
+            AccountMeta::new(*profile, false),                    // 4 - profile
+            AccountMeta::new_readonly(spl_token::id(), false),    // 5 - spl token program
+            AccountMeta::new_readonly(Rent::id(), false),         // 6 - rent
+            AccountMeta::new_readonly(system_program::ID, false), // 7 - system program
+        ];
+
+        if first_purchase {
+            accounts.extend(vec![
+                AccountMeta::new(*metadata, false), // 8 - metadata account
+                AccountMeta::new_readonly(self.metadata_program, false), // 9 - metaplex program
+                AccountMeta::new(*token_account, false), // 10 - token account
+            ])
+        } else {
+            accounts.extend(vec![
+                AccountMeta::new_readonly(system_program::ID, false), // 8 - placeholder
+                AccountMeta::new_readonly(system_program::ID, false), // 9 - placeholder
+                AccountMeta::new(*token_account, false),              // 10 - token account
+            ])
+        }
+
+        accounts.extend(vec![
+            AccountMeta::new(self.vault, false), // 11 - team vault most likely
+            AccountMeta::new_readonly(associated_token::ID, false), // 12 - ata program
+        ]);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: args.to_instruction_data(),
+        }
+    }
+
+    pub fn withdraw(
+        &self,
+        user: &Pubkey,
+        bank: &Pubkey,
+        config: &Pubkey,
+        token_mint: &Pubkey,
+        profile: &Pubkey,
+        id: u64,
+    ) -> Instruction {
+        self.withdraw_with_args(
+            user,
+            bank,
+            config,
+            token_mint,
+            profile,
+            &WithdrawArgs {
+                version: VERSION,
+                id,
+            },
+        )
+    }
+
+    /// A withdraw encoding the args as given, e.g. to choose the version.
+    pub fn withdraw_with_args(
+        &self,
+        user: &Pubkey,
+        bank: &Pubkey,
+        config: &Pubkey,
+        token_mint: &Pubkey,
+        profile: &Pubkey,
+        args: &WithdrawArgs,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*user, true),                        // 0 - user
+                AccountMeta::new(*bank, false),                       // 1 - bank
+                AccountMeta::new(*config, false),                     // 2 - config
+                AccountMeta::new(*token_mint, false),                 // 3 - token mint
+                AccountMeta::new(*profile, false),                    // 4 - profile
+                AccountMeta::new_readonly(spl_token::id(), false),    // 5 - spl token program
+                AccountMeta::new_readonly(Rent::id(), false),         // 6 - rent
+                AccountMeta::new_readonly(system_program::ID, false), // 7 - system program
+                AccountMeta::new_readonly(system_program::ID, false), // 8 - placeholder
+                AccountMeta::new_readonly(system_program::ID, false), // 9 - placeholder
+                AccountMeta::new_readonly(system_program::ID, false), // 10 - placeholder
+            ],
+            data: args.to_instruction_data(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn swap(
     user: &Pubkey,
     bank: &Pubkey,
@@ -107,41 +409,16 @@ pub fn swap(
     price: u64,
     side: Side,
 ) -> Instruction {
-    let mut accounts = vec![
-        AccountMeta::new(*user, true),                        // 0 - user
-        AccountMeta::new(*bank, false),                       // 1 - bank
-        AccountMeta::new(*config, false),                     // 2 - config
-        AccountMeta::new(*token_mint, false),                 // 3 - token mint
-        AccountMeta::new(*profile, false),                    // 4 - profile
-        AccountMeta::new_readonly(spl_token::id(), false),    // 5 - spl token program
-        AccountMeta::new_readonly(Rent::id(), false),         // 6 - rent
-        AccountMeta::new_readonly(system_program::ID, false), // 7 - system program
-    ];
-
-    if first_purchase {
-        accounts.extend(vec![
-            AccountMeta::new(*metadata, false), // 8 - metadata account
-            AccountMeta::new_readonly(mpl_token_metadata::ID, false), // 9 - metaplex program
-            AccountMeta::new(*token_account, false), // 10 - token account
-        ])
-    } else {
-        accounts.extend(vec![
-            AccountMeta::new_readonly(system_program::ID, false), // 8 - placeholder
-            AccountMeta::new_readonly(system_program::ID, false), // 9 - placeholder
-            AccountMeta::new(*token_account, false),              // 10 - token account
-        ])
-    }
-
-    accounts.extend(vec![
-        AccountMeta::new(crate::vault::id(), false), // 11 - team vault most likely
-        AccountMeta::new_readonly(associated_token::ID, false), // 12 - ata program
-    ]);
-
-    Instruction {
-        program_id: crate::id(),
-        accounts,
-        data: create_swap_instruction_data(id, amount, price, side),
-    }
+    let accounts = SwapAccounts {
+        user: *user,
+        bank: *bank,
+        config: *config,
+        token_mint: *token_mint,
+        profile: *profile,
+        metadata: *metadata,
+        token_account: *token_account,
+    };
+    FriendzyProgram::MAINNET.swap(&accounts, first_purchase, id, amount, price, side)
 }
 
 pub fn withdraw(
@@ -152,67 +429,56 @@ pub fn withdraw(
     profile: &Pubkey,
     id: u64,
 ) -> Instruction {
-    Instruction {
-        program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new(*user, true),                        // 0 - user
-            AccountMeta::new(*bank, false),                       // 1 - bank
-            AccountMeta::new(*config, false),                     // 2 - config
-            AccountMeta::new(*token_mint, false),                 // 3 - token mint
-            AccountMeta::new(*profile, false),                    // 4 - profile
-            AccountMeta::new_readonly(spl_token::id(), false),    // 5 - spl token program
-            AccountMeta::new_readonly(Rent::id(), false),         // 6 - rent
-            AccountMeta::new_readonly(system_program::ID, false), // 7 - system program
-            AccountMeta::new_readonly(system_program::ID, false), // 8 - placeholder
-            AccountMeta::new_readonly(system_program::ID, false), // 9 - placeholder
-            AccountMeta::new_readonly(system_program::ID, false), // 10 - placeholder
-        ],
-        data: create_withdraw_instruction_data(id),
-    }
+    FriendzyProgram::MAINNET.withdraw(user, bank, config, token_mint, profile, id)
 }
 
 /// Creates a "swap" instruction.
 /// Buy: [0, id, 1, amount, max_price]
 /// Sell: [0, id, 2, amount, min_price]
-fn create_swap_instruction_data(id: u64, amount: u64, price: u64, side: Side) -> Vec<u8> {
-    // 0 - optional
-    // 1 - twitter/x user_id that is commonly found in the API responses
-    // 9 - the side, either `Default` = 0 | `Buy` = 1 | `Sell` = 2 with u8 alignment or sell
-    // 10 - the amount of keys to buy, this is denominated in native units
-    // 18 - the price, !!!!! IMPORTANT !!!!! this will be `max_price` for Side == Side::Buy otherwise
-    // 26 - !!!!! IMPORTANT !!!!! there appears to be another number at the end of the instruction but we don't use it
-    [
-        vec![0],
-        id.try_to_vec().unwrap_or_default(),
-        side.try_to_vec().unwrap_or_default(),
-        amount.try_to_vec().unwrap_or_default(),
-        price.try_to_vec().unwrap_or_default(),
-    ]
-    .concat()
+pub fn create_swap_instruction_data(id: u64, amount: u64, price: u64, side: Side) -> Vec<u8> {
+    create_swap_instruction_data_with_extra(id, amount, price, side, None)
+}
+
+/// Creates a "swap" instruction in the extended layout when `extra` is set.
+///
+/// The extended layout is unverified, the program may reject it.
+/// Buy: [0, id, 1, amount, max_price, extra]
+/// Sell: [0, id, 2, amount, min_price, extra]
+pub fn create_swap_instruction_data_with_extra(
+    id: u64,
+    amount: u64,
+    price: u64,
+    side: Side,
+    extra: Option<u64>,
+) -> Vec<u8> {
+    SwapArgs {
+        version: VERSION,
+        id,
+        side,
+        amount,
+        price,
+        extra,
+    }
+    .to_instruction_data()
 }
 
 /// Withdraw: [0, id, 3]
-fn create_withdraw_instruction_data(id: u64) -> Vec<u8> {
-    // 0 - optional
-    // 1 - twitter/x user_id that is commonly found in the API responses
-    // 9 - instruction
-    [vec![0], id.try_to_vec().unwrap_or_default(), vec![3]].concat()
+pub fn create_withdraw_instruction_data(id: u64) -> Vec<u8> {
+    WithdrawArgs {
+        version: VERSION,
+        id,
+    }
+    .to_instruction_data()
 }
 
 /// Verify: [0, id, 0, owner]
-#[allow(dead_code)]
-fn create_verify_instruction_data(owner: &Pubkey, id: u64) -> Vec<u8> {
-    // 0 - optional
-    // 1 - twitter/x user_id
-    // 9 - instruction
-    // 10 - owner
-    [
-        vec![0],
-        id.try_to_vec().unwrap_or_default(),
-        vec![0],
-        owner.try_to_vec().unwrap_or_default(),
-    ]
-    .concat()
+pub fn create_verify_instruction_data(owner: &Pubkey, id: u64) -> Vec<u8> {
+    VerifyArgs {
+        version: VERSION,
+        id,
+        owner: *owner,
+    }
+    .to_instruction_data()
 }
 
 #[cfg(test)]
@@ -220,7 +486,6 @@ mod tests {
     use super::*;
     use crate::pda::*;
     use base64::{engine::general_purpose, Engine};
-    use spl_associated_token_account::get_associated_token_address;
     use std::str::FromStr;
 
     fn decode_base64(data: &str) -> Vec<u8> {
@@ -288,6 +553,7 @@ mod tests {
         assert_eq!(10000000000, swap_args.amount);
         assert_eq!(Side::Buy, swap_args.side);
         assert_eq!(478333334, swap_args.price);
+        assert_eq!(None, swap_args.extra);
 
         let ix_data = create_swap_instruction_data(
             1_162_302_698_118_684_672,
@@ -300,6 +566,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_swap_extended_instruction_data() -> Result<()> {
+        let data = decode_base64("AACg11IlVCEQAQDkC1QCAAAAlsmCHAAAAAAqAAAAAAAAAA==");
+        assert_eq!(SwapArgs::EXTENDED_LEN, data.len());
+        let swap_args = SwapArgs::try_from_slice(&data).unwrap();
+        assert_eq!(1_162_302_698_118_684_672, swap_args.id);
+        assert_eq!(478333334, swap_args.price);
+        assert_eq!(Some(42), swap_args.extra);
+        assert_eq!(data, swap_args.to_instruction_data());
+
+        let short = SwapArgs {
+            extra: None,
+            ..swap_args
+        };
+        assert_eq!(data[..SwapArgs::LEN], short.to_instruction_data());
+
+        assert!(SwapArgs::try_from_slice(&data[..30]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_instruction_version() -> Result<()> {
+        let mut data = create_swap_instruction_data(42, 1, 2, Side::Sell);
+        let instruction = FriendzyInstruction::try_from_slice(&data).unwrap();
+        assert_eq!(VERSION, instruction.version());
+        assert_eq!(42, instruction.id());
+        assert_eq!(data, instruction.to_instruction_data());
+
+        // unknown versions are reported with the version found
+        data[SwapArgs::VERSION_INDEX] = 7;
+        let err = SwapArgs::try_from_slice(&data).unwrap_err();
+        assert_eq!(err, error!(InstructionError::UnknownVersion));
+        let Error::AnchorError(anchor_error) = &err else {
+            panic!("{}", err);
+        };
+        assert!(matches!(
+            &anchor_error.compared_values,
+            Some(anchor_lang::error::ComparedValues::Values((version, _))) if version == "7"
+        ));
+        assert!(FriendzyInstruction::try_from_slice(&data).is_err());
+        assert!(read_version(&[]).is_err());
+
+        // a version can still be chosen when encoding
+        let withdraw = WithdrawArgs { version: 7, id: 42 };
+        let data = withdraw.to_instruction_data();
+        assert_eq!(7, data[WithdrawArgs::VERSION_INDEX]);
+        assert_eq!(err, FriendzyInstruction::try_from_slice(&data).unwrap_err());
+
+        // and through the builders
+        let program = FriendzyProgram::MAINNET;
+        let (user, key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = program.withdraw_with_args(&user, &key, &key, &key, &key, &withdraw);
+        assert_eq!(data, instruction.data);
+        assert_eq!(11, instruction.accounts.len());
+
+        let swap = SwapArgs {
+            version: 7,
+            id: 42,
+            side: Side::Buy,
+            amount: 1,
+            price: 2,
+            extra: Some(3),
+        };
+        let accounts = program.swap_accounts(42, &user);
+        let instruction = program.swap_with_args(&accounts, false, &swap);
+        assert_eq!(swap.to_instruction_data(), instruction.data);
+        assert_eq!(7, instruction.data[SwapArgs::VERSION_INDEX]);
+        assert_eq!(SwapArgs::EXTENDED_LEN, instruction.data.len());
+        let mut data = instruction.data;
+        data[SwapArgs::VERSION_INDEX] = VERSION;
+        assert_eq!(
+            SwapArgs { version: 0, ..swap },
+            SwapArgs::try_from_slice(&data).unwrap()
+        );
+
+        let mut data = create_withdraw_instruction_data(42);
+        data[WithdrawArgs::PADDING_INDEX] = 9;
+        assert_eq!(
+            Error::from(ProgramError::InvalidInstructionData),
+            FriendzyInstruction::try_from_slice(&data).unwrap_err()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_swap_sell_instruction_data() -> Result<()> {
         let data = decode_base64("AACg11IlVCEQAgB0O6QLAAAAZcBHDgAAAAA=");
@@ -0,0 +1,426 @@
+//! JSON shapes matching the TypeScript package, enabled with the `serde` feature.
+//!
+//! Pubkeys are base58 strings and u64s are decimal strings, X ids like 1_436_880_221_354_045_450
+//! don't fit in a JS number. Native amounts come with a UI-unit companion field which is ignored
+//! when deserializing.
+
+use anchor_lang::prelude::Pubkey;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ui, Config, Profile, Side, SwapArgs, VerifyArgs, WithdrawArgs};
+
+/// (De)serializes a u64 as a decimal string, numbers are also accepted when deserializing.
+pub mod u64_string {
+    use serde::{de, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        struct U64Visitor;
+
+        impl<'de> de::Visitor<'de> for U64Visitor {
+            type Value = u64;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a u64 as a string or number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+                Ok(value)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(U64Visitor)
+    }
+}
+
+/// (De)serializes an optional u64 as a decimal string or null.
+pub mod option_u64_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::u64_string")] u64);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(value)| value))
+    }
+}
+
+/// (De)serializes a pubkey as a base58 string.
+pub mod pubkey_string {
+    use anchor_lang::prelude::Pubkey;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(value: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Pubkey::from_str(&value).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigJson {
+    #[serde(with = "u64_string")]
+    id: u64,
+    #[serde(with = "u64_string")]
+    supply: u64,
+    #[serde(with = "pubkey_string")]
+    owner: Pubkey,
+    #[serde(with = "u64_string")]
+    royalties: u64,
+    #[serde(with = "u64_string")]
+    unclaimed: u64,
+    #[serde(with = "u64_string")]
+    debt: u64,
+    #[serde(skip_deserializing)]
+    supply_ui: f64,
+    #[serde(skip_deserializing)]
+    royalties_ui: f64,
+    #[serde(skip_deserializing)]
+    unclaimed_ui: f64,
+    #[serde(skip_deserializing)]
+    claimed_royalties_ui: f64,
+}
+
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConfigJson {
+            id: self.id,
+            supply: self.supply,
+            owner: self.owner,
+            royalties: self.royalties,
+            unclaimed: self.unclaimed,
+            debt: self.debt,
+            supply_ui: ui(self.supply),
+            royalties_ui: ui(self.royalties),
+            unclaimed_ui: ui(self.unclaimed),
+            claimed_royalties_ui: ui(self.royalties.saturating_sub(self.unclaimed)),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = ConfigJson::deserialize(deserializer)?;
+        Ok(Self {
+            id: json.id,
+            supply: json.supply,
+            owner: json.owner,
+            royalties: json.royalties,
+            unclaimed: json.unclaimed,
+            debt: json.debt,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileJson {
+    #[serde(with = "u64_string")]
+    id: u64,
+    #[serde(with = "pubkey_string")]
+    owner: Pubkey,
+    #[serde(with = "u64_string")]
+    buy_amount: u64,
+    #[serde(with = "u64_string")]
+    sell_amount: u64,
+    #[serde(with = "u64_string")]
+    buy_volume: u64,
+    #[serde(with = "u64_string")]
+    sell_volume: u64,
+    #[serde(with = "u64_string")]
+    reserved: u64,
+    #[serde(skip_deserializing)]
+    buy_amount_ui: f64,
+    #[serde(skip_deserializing)]
+    sell_amount_ui: f64,
+    #[serde(skip_deserializing)]
+    buy_volume_ui: f64,
+    #[serde(skip_deserializing)]
+    sell_volume_ui: f64,
+}
+
+impl Serialize for Profile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProfileJson {
+            id: self.id,
+            owner: self.owner,
+            buy_amount: self.buy_amount,
+            sell_amount: self.sell_amount,
+            buy_volume: self.buy_volume,
+            sell_volume: self.sell_volume,
+            reserved: self.reserved,
+            buy_amount_ui: ui(self.buy_amount),
+            sell_amount_ui: ui(self.sell_amount),
+            buy_volume_ui: ui(self.buy_volume),
+            sell_volume_ui: ui(self.sell_volume),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Profile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = ProfileJson::deserialize(deserializer)?;
+        Ok(Self {
+            id: json.id,
+            owner: json.owner,
+            buy_amount: json.buy_amount,
+            sell_amount: json.sell_amount,
+            buy_volume: json.buy_volume,
+            sell_volume: json.sell_volume,
+            reserved: json.reserved,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapArgsJson {
+    #[serde(default)]
+    version: u8,
+    #[serde(with = "u64_string")]
+    id: u64,
+    side: Side,
+    #[serde(with = "u64_string")]
+    amount: u64,
+    #[serde(with = "u64_string")]
+    price: u64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_u64_string"
+    )]
+    extra: Option<u64>,
+    #[serde(skip_deserializing)]
+    amount_ui: f64,
+    #[serde(skip_deserializing)]
+    price_ui: f64,
+}
+
+impl Serialize for SwapArgs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SwapArgsJson {
+            version: self.version,
+            id: self.id,
+            side: self.side,
+            amount: self.amount,
+            price: self.price,
+            extra: self.extra,
+            amount_ui: ui(self.amount),
+            price_ui: ui(self.price),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SwapArgs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = SwapArgsJson::deserialize(deserializer)?;
+        Ok(Self {
+            version: json.version,
+            id: json.id,
+            side: json.side,
+            amount: json.amount,
+            price: json.price,
+            extra: json.extra,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WithdrawArgsJson {
+    #[serde(default)]
+    version: u8,
+    #[serde(with = "u64_string")]
+    id: u64,
+}
+
+impl Serialize for WithdrawArgs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WithdrawArgsJson {
+            version: self.version,
+            id: self.id,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WithdrawArgs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = WithdrawArgsJson::deserialize(deserializer)?;
+        Ok(Self {
+            version: json.version,
+            id: json.id,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifyArgsJson {
+    #[serde(default)]
+    version: u8,
+    #[serde(with = "u64_string")]
+    id: u64,
+    #[serde(with = "pubkey_string")]
+    owner: Pubkey,
+}
+
+impl Serialize for VerifyArgs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VerifyArgsJson {
+            version: self.version,
+            id: self.id,
+            owner: self.owner,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VerifyArgs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = VerifyArgsJson::deserialize(deserializer)?;
+        Ok(Self {
+            version: json.version,
+            id: json.id,
+            owner: json.owner,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_config_json() -> Result<()> {
+        let config = Config {
+            id: 1_436_880_221_354_045_450,
+            supply: 203_000_000_000,
+            owner: Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap(),
+            royalties: 1_178_606_656,
+            unclaimed: 164_666_665,
+            debt: 0,
+        };
+
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            json!({
+                "id": "1436880221354045450",
+                "supply": "203000000000",
+                "owner": "hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh",
+                "royalties": "1178606656",
+                "unclaimed": "164666665",
+                "debt": "0",
+                "supplyUi": 203.0,
+                "royaltiesUi": 1.178606656,
+                "unclaimedUi": 0.164666665,
+                "claimedRoyaltiesUi": 1.013939991,
+            }),
+            value
+        );
+
+        let decoded: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_json_accepts_numbers() -> Result<()> {
+        let profile: Profile = serde_json::from_value(json!({
+            "id": 1_162_302_698_118_684_672u64,
+            "owner": "hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh",
+            "buyAmount": "44000000000",
+            "sellAmount": "0",
+            "buyVolume": "1535166667",
+            "sellVolume": "0",
+            "reserved": "0",
+        }))
+        .unwrap();
+
+        assert_eq!(1_162_302_698_118_684_672, profile.id);
+        assert_eq!(44_000_000_000, profile.buy_amount);
+
+        let value = serde_json::to_value(&profile).unwrap();
+        assert_eq!(json!("1162302698118684672"), value["id"]);
+        assert_eq!(json!(44.0), value["buyAmountUi"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_args_json() -> Result<()> {
+        let swap = SwapArgs {
+            version: 0,
+            id: 1_436_880_221_354_045_450,
+            side: Side::Sell,
+            amount: 1_500_000_000,
+            price: 478_333_334,
+            extra: None,
+        };
+        let value = serde_json::to_value(&swap).unwrap();
+        assert_eq!(json!("Sell"), value["side"]);
+        assert!(value.get("extra").is_none());
+        assert_eq!(json!("1436880221354045450"), value["id"]);
+        assert_eq!(json!(1.5), value["amountUi"]);
+        assert_eq!(swap, serde_json::from_value(value).unwrap());
+
+        let extended = SwapArgs {
+            extra: Some(42),
+            ..swap
+        };
+        let value = serde_json::to_value(&extended).unwrap();
+        assert_eq!(json!("42"), value["extra"]);
+        assert_eq!(extended, serde_json::from_value(value).unwrap());
+
+        let withdraw = WithdrawArgs { version: 0, id: 42 };
+        let value = serde_json::to_value(&withdraw).unwrap();
+        assert_eq!(json!({ "version": 0, "id": "42" }), value);
+        assert_eq!(withdraw, serde_json::from_value(value).unwrap());
+        // the version defaults to 0 when missing
+        assert_eq!(
+            withdraw,
+            serde_json::from_value(json!({ "id": 42 })).unwrap()
+        );
+
+        let verify = VerifyArgs {
+            version: 0,
+            id: 42,
+            owner: Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap(),
+        };
+        let value = serde_json::to_value(&verify).unwrap();
+        assert_eq!(
+            json!({ "version": 0, "id": "42", "owner": "hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh" }),
+            value
+        );
+        assert_eq!(verify, serde_json::from_value(value).unwrap());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+use anchor_lang::prelude::Pubkey;
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{history::spot_price, Config, Profile, DECIMAL_DENOMINATOR};
+
+/// An id ranked by its `Config`, amounts are in native units and lamports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRank {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub supply: u64,
+    /// The curve cost of the next whole key.
+    pub price: u64,
+    /// The supply valued at `price`, saturating at `u64::MAX`.
+    pub market_cap: u64,
+    pub royalties: u64,
+}
+
+/// A wallet ranked by its `Profile`s summed across every id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraderRank {
+    pub trader: Pubkey,
+    pub buy_amount: u64,
+    pub sell_amount: u64,
+    pub buy_volume: u64,
+    pub sell_volume: u64,
+    /// The ids the wallet has a profile for.
+    pub ids: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRanking {
+    Supply,
+    MarketCap,
+    Royalties,
+}
+
+impl KeyRanking {
+    pub const ALL: [KeyRanking; 3] = [
+        KeyRanking::Supply,
+        KeyRanking::MarketCap,
+        KeyRanking::Royalties,
+    ];
+
+    fn value(self, key: &KeyRank) -> u64 {
+        match self {
+            KeyRanking::Supply => key.supply,
+            KeyRanking::MarketCap => key.market_cap,
+            KeyRanking::Royalties => key.royalties,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraderRanking {
+    BuyVolume,
+    SellVolume,
+}
+
+impl TraderRanking {
+    pub const ALL: [TraderRanking; 2] = [TraderRanking::BuyVolume, TraderRanking::SellVolume];
+
+    fn value(self, trader: &TraderRank) -> u64 {
+        match self {
+            TraderRanking::BuyVolume => trader.buy_volume,
+            TraderRanking::SellVolume => trader.sell_volume,
+        }
+    }
+}
+
+/// A page of a ranking, `page` starts at 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: usize,
+    pub per_page: usize,
+    /// The ranked entries across every page.
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    pub fn pages(&self) -> usize {
+        self.total.div_ceil(self.per_page.max(1))
+    }
+}
+
+/// The indexes of `items` sorted by `key`.
+fn rank<T, K: Ord>(items: &[T], key: impl Fn(&T) -> K) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by_key(|index| key(&items[*index]));
+    order
+}
+
+/// Slices a page of `items` in the order of `ranked`, a list of indexes into `items`.
+fn page<T: Copy>(items: &[T], ranked: &[usize], page: usize, per_page: usize) -> Page<T> {
+    let per_page = per_page.max(1);
+    let items = ranked
+        .iter()
+        .skip(page.saturating_mul(per_page))
+        .take(per_page)
+        .map(|index| items[*index])
+        .collect();
+    Page {
+        items,
+        page,
+        per_page,
+        total: ranked.len(),
+    }
+}
+
+/// Rankings of every key and trader from a full scan of `Config`s and `Profile`s.
+///
+/// Every ranking is sorted once when the leaderboard is built, pages are sliced from it. Ties are
+/// broken by id and by trader address so pages are stable.
+#[derive(Debug, Default, Clone)]
+pub struct Leaderboard {
+    keys: Vec<KeyRank>,
+    traders: Vec<TraderRank>,
+    /// The indexes of `keys` sorted by each ranking of [`KeyRanking::ALL`].
+    key_rankings: [Vec<usize>; 3],
+    /// The indexes of `traders` sorted by each ranking of [`TraderRanking::ALL`].
+    trader_rankings: [Vec<usize>; 2],
+}
+
+impl Leaderboard {
+    pub fn new(configs: &[Config], profiles: &[Profile]) -> Self {
+        let keys: Vec<KeyRank> = configs
+            .iter()
+            .map(|config| {
+                let price = spot_price(config.supply);
+                let market_cap =
+                    price as u128 * config.supply as u128 / DECIMAL_DENOMINATOR as u128;
+                KeyRank {
+                    id: config.id,
+                    owner: config.owner,
+                    supply: config.supply,
+                    price,
+                    market_cap: u64::try_from(market_cap).unwrap_or(u64::MAX),
+                    royalties: config.royalties,
+                }
+            })
+            .collect();
+
+        let mut traders: HashMap<Pubkey, TraderRank> = HashMap::new();
+        for profile in profiles {
+            let trader = traders.entry(profile.owner).or_insert(TraderRank {
+                trader: profile.owner,
+                buy_amount: 0,
+                sell_amount: 0,
+                buy_volume: 0,
+                sell_volume: 0,
+                ids: 0,
+            });
+            trader.buy_amount = trader.buy_amount.saturating_add(profile.buy_amount);
+            trader.sell_amount = trader.sell_amount.saturating_add(profile.sell_amount);
+            trader.buy_volume = trader.buy_volume.saturating_add(profile.buy_volume);
+            trader.sell_volume = trader.sell_volume.saturating_add(profile.sell_volume);
+            trader.ids += 1;
+        }
+
+        let traders: Vec<TraderRank> = traders.into_values().collect();
+
+        let key_rankings =
+            KeyRanking::ALL.map(|ranking| rank(&keys, |key| (Reverse(ranking.value(key)), key.id)));
+        let trader_rankings = TraderRanking::ALL.map(|ranking| {
+            rank(&traders, |trader| {
+                (Reverse(ranking.value(trader)), trader.trader)
+            })
+        });
+
+        Self {
+            keys,
+            traders,
+            key_rankings,
+            trader_rankings,
+        }
+    }
+
+    /// Returns a page of the keys sorted by the ranking, highest first.
+    pub fn keys(&self, ranking: KeyRanking, page_index: usize, per_page: usize) -> Page<KeyRank> {
+        let ranked = &self.key_rankings[ranking as usize];
+        page(&self.keys, ranked, page_index, per_page)
+    }
+
+    /// Returns a page of the traders sorted by the ranking, highest first.
+    pub fn traders(
+        &self,
+        ranking: TraderRanking,
+        page_index: usize,
+        per_page: usize,
+    ) -> Page<TraderRank> {
+        let ranked = &self.trader_rankings[ranking as usize];
+        page(&self.traders, ranked, page_index, per_page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::*;
+
+    fn config(id: u64, keys: u64, royalties: u64) -> Config {
+        Config {
+            id,
+            supply: keys * DECIMAL_DENOMINATOR,
+            royalties,
+            ..Config::default()
+        }
+    }
+
+    fn profile(id: u64, owner: Pubkey, buy_volume: u64, sell_volume: u64) -> Profile {
+        Profile {
+            id,
+            owner,
+            buy_volume,
+            sell_volume,
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn test_key_rankings() -> Result<()> {
+        let leaderboard = Leaderboard::new(
+            &[
+                config(1, 10, 300),
+                config(2, 30, 100),
+                config(3, 20, 200),
+                config(4, 20, 0),
+            ],
+            &[],
+        );
+
+        let ids = |page: Page<KeyRank>| page.items.iter().map(|k| k.id).collect::<Vec<u64>>();
+        assert_eq!(
+            vec![2, 3, 4, 1],
+            ids(leaderboard.keys(KeyRanking::Supply, 0, 10))
+        );
+        assert_eq!(
+            vec![1, 3],
+            ids(leaderboard.keys(KeyRanking::Royalties, 0, 2))
+        );
+        assert_eq!(
+            vec![2, 4],
+            ids(leaderboard.keys(KeyRanking::Royalties, 1, 2))
+        );
+
+        let page = leaderboard.keys(KeyRanking::MarketCap, 0, 1);
+        assert_eq!(2, page.items[0].id);
+        assert_eq!(
+            spot_price(30 * DECIMAL_DENOMINATOR) * 30,
+            page.items[0].market_cap
+        );
+        assert_eq!(4, page.pages());
+        assert!(leaderboard
+            .keys(KeyRanking::MarketCap, 4, 1)
+            .items
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trader_rankings() -> Result<()> {
+        let (whale, flipper) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let leaderboard = Leaderboard::new(
+            &[],
+            &[
+                profile(1, whale, 500, 0),
+                profile(2, whale, 700, 100),
+                profile(1, flipper, 1_000, 1_100),
+            ],
+        );
+
+        let page = leaderboard.traders(TraderRanking::BuyVolume, 0, 10);
+        assert_eq!(2, page.total);
+        assert_eq!(whale, page.items[0].trader);
+        assert_eq!(1_200, page.items[0].buy_volume);
+        assert_eq!(2, page.items[0].ids);
+
+        let page = leaderboard.traders(TraderRanking::SellVolume, 0, 1);
+        assert_eq!(flipper, page.items[0].trader);
+        assert_eq!(2, page.pages());
+
+        Ok(())
+    }
+}
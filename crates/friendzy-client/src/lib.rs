@@ -1,12 +1,80 @@
+// The decoders and address helpers return anchor's `Result` like the program does, its `Error`
+// carries the source location and compared values and is larger than clippy allows.
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
 
+mod backtest;
+#[cfg(feature = "client")]
+mod batch;
+mod cache;
+#[cfg(feature = "client")]
+mod compute;
+mod creator;
+mod diff;
+#[cfg(feature = "client")]
+mod error;
+mod explain;
+mod fees;
+mod history;
+#[cfg(feature = "client")]
+mod holders;
+#[cfg(feature = "serde")]
+mod idl;
 mod instructions;
+#[cfg(feature = "serde")]
+pub mod json;
+mod leaderboard;
+#[cfg(feature = "client")]
+mod loader;
+mod lookup;
+#[cfg(feature = "client")]
+mod orders;
 mod pda;
+mod program;
+#[cfg(feature = "client")]
+mod scan;
+#[cfg(feature = "client")]
+mod snipe;
 mod state;
-
+#[cfg(feature = "client")]
+mod transaction;
+mod validate;
+
+pub use backtest::*;
+#[cfg(feature = "client")]
+pub use batch::*;
+pub use cache::*;
+#[cfg(feature = "client")]
+pub use compute::*;
+pub use creator::*;
+pub use diff::*;
+#[cfg(feature = "client")]
+pub use error::*;
+pub use explain::*;
+pub use fees::*;
+pub use history::*;
+#[cfg(feature = "client")]
+pub use holders::*;
+#[cfg(feature = "serde")]
+pub use idl::*;
 pub use instructions::*;
+pub use leaderboard::*;
+#[cfg(feature = "client")]
+pub use loader::*;
+pub use lookup::*;
+#[cfg(feature = "client")]
+pub use orders::*;
 pub use pda::*;
+pub use program::*;
+#[cfg(feature = "client")]
+pub use scan::*;
+#[cfg(feature = "client")]
+pub use snipe::*;
 pub use state::*;
+#[cfg(feature = "client")]
+pub use transaction::*;
+pub use validate::*;
 
 declare_id!("FrenAezyygcqNKaCkYNzBAxTCo717wh1bgnKLqnxP8Cq");
 
@@ -40,7 +108,32 @@ pub fn calculate_price(supply: u64) -> u64 {
 }
 
 pub fn calculate_price_ui(supply: f64) -> f64 {
-    calculate_price(supply as u64) as f64 / DECIMAL_DENOMINATOR as f64
+    ui(calculate_price(supply as u64))
+}
+
+/// Converts a native amount to UI units.
+pub(crate) fn ui(amount: u64) -> f64 {
+    amount as f64 / DECIMAL_DENOMINATOR as f64
+}
+
+/// The curve evaluated with integer math, the same way the TypeScript package does it.
+///
+/// `None` when the square overflows, past roughly 1.8e19 native units.
+fn curve_exact(point: u64) -> Option<u128> {
+    let point = INITIAL_POINT as u128 + point as u128;
+    Some(point.checked_mul(point)? / CURVE_DENOMINATOR as u128)
+}
+
+/// The cost of buying `amount` keys at the given supply, both in native units.
+pub fn calculate_buy_cost(supply: u64, amount: u64) -> Option<u64> {
+    let end = supply.checked_add(amount)?;
+    u64::try_from(curve_exact(end)? - curve_exact(supply)?).ok()
+}
+
+/// The proceeds of selling `amount` keys at the given supply, both in native units.
+pub fn calculate_sell_proceeds(supply: u64, amount: u64) -> Option<u64> {
+    let end = supply.checked_sub(amount)?;
+    u64::try_from(curve_exact(supply)? - curve_exact(end)?).ok()
 }
 
 #[cfg(test)]
@@ -61,6 +154,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_calculate_buy_cost() -> Result<()> {
+        assert_eq!(Some(10_000_000), calculate_buy_cost(0, 1_000_000_000));
+        assert_eq!(
+            Some(10_166_667),
+            calculate_buy_cost(1_000_000_000, 1_000_000_000)
+        );
+        assert_eq!(Some(20_166_667), calculate_buy_cost(0, 2_000_000_000));
+        assert_eq!(None, calculate_buy_cost(u64::MAX, 1));
+        assert_eq!(None, calculate_buy_cost(0, u64::MAX));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_calculate_sell_proceeds() -> Result<()> {
+        assert_eq!(
+            Some(10_166_667),
+            calculate_sell_proceeds(2_000_000_000, 1_000_000_000)
+        );
+        assert_eq!(
+            calculate_buy_cost(5_000_000_000, 3_000_000_000),
+            calculate_sell_proceeds(8_000_000_000, 3_000_000_000)
+        );
+        assert_eq!(None, calculate_sell_proceeds(1_000_000_000, 2_000_000_000));
+        assert_eq!(None, calculate_sell_proceeds(u64::MAX, 1));
+        Ok(())
+    }
+
     #[test]
     pub fn test_calculate_price_zero_supply_ui() -> Result<()> {
         let price = calculate_price_ui(0f64);
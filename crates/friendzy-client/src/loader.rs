@@ -0,0 +1,201 @@
+use anchor_lang::AnchorDeserialize;
+use solana_client::{rpc_client::RpcClient, rpc_request::MAX_MULTIPLE_ACCOUNTS};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::thread;
+
+use crate::{error::ClientError, program::FriendzyProgram, Config, Profile};
+
+/// The `getMultipleAccounts` calls in flight by default.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Loads many accounts with `getMultipleAccounts`, in chunks of [`MAX_MULTIPLE_ACCOUNTS`] sent
+/// from up to `concurrency` threads at once.
+pub struct BatchLoader<'a> {
+    rpc_client: &'a RpcClient,
+    program: FriendzyProgram,
+    concurrency: usize,
+}
+
+impl<'a> BatchLoader<'a> {
+    pub fn new(rpc_client: &'a RpcClient) -> Self {
+        Self {
+            rpc_client,
+            program: FriendzyProgram::MAINNET,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    pub fn with_program(mut self, program: FriendzyProgram) -> Self {
+        self.program = program;
+        self
+    }
+
+    /// Sets the chunks fetched at once, at least 1.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Fetches the accounts in the order of the addresses, `None` for missing ones.
+    pub fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        let chunks: Vec<&[Pubkey]> = addresses.chunks(MAX_MULTIPLE_ACCOUNTS).collect();
+        let mut accounts = Vec::with_capacity(addresses.len());
+
+        for group in chunks.chunks(self.concurrency) {
+            let fetched = thread::scope(|scope| {
+                let handles: Vec<_> = group
+                    .iter()
+                    .map(|chunk| {
+                        scope.spawn(|| {
+                            self.rpc_client
+                                .get_multiple_accounts(chunk)
+                                .map_err(ClientError::from)
+                        })
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Result<Vec<_>, ClientError>>()
+            })?;
+            accounts.extend(fetched.into_iter().flatten());
+        }
+
+        Ok(accounts)
+    }
+
+    /// Fetches the `Config` of every id, `None` when it doesn't exist.
+    ///
+    /// An account that exists but can't be decoded fails the whole call with
+    /// [`ClientError::Undecodable`].
+    pub fn get_configs(&self, ids: &[u64]) -> Result<Vec<Option<Config>>, ClientError> {
+        let addresses: Vec<Pubkey> = ids
+            .iter()
+            .map(|id| self.program.derive_config_address(*id).0)
+            .collect();
+        let accounts = self.get_multiple_accounts(&addresses)?;
+        decode_accounts(&addresses, accounts)
+    }
+
+    /// Fetches the `Profile` of every (id, user), `None` when it doesn't exist.
+    ///
+    /// An account that exists but can't be decoded fails the whole call with
+    /// [`ClientError::Undecodable`].
+    pub fn get_profiles(
+        &self,
+        profiles: &[(u64, Pubkey)],
+    ) -> Result<Vec<Option<Profile>>, ClientError> {
+        let addresses: Vec<Pubkey> = profiles
+            .iter()
+            .map(|(id, user)| self.program.derive_profile_address(*id, user).0)
+            .collect();
+        let accounts = self.get_multiple_accounts(&addresses)?;
+        decode_accounts(&addresses, accounts)
+    }
+}
+
+fn decode_accounts<T: AnchorDeserialize>(
+    addresses: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<Option<T>>, ClientError> {
+    addresses
+        .iter()
+        .zip(accounts)
+        .map(|(address, account)| {
+            account
+                .map(|account| {
+                    T::try_from_slice(&account.data).map_err(|_| ClientError::Undecodable(*address))
+                })
+                .transpose()
+        })
+        .collect()
+}
+
+/// Fetches the mainnet `Config` of every id, see [`BatchLoader::get_configs`].
+pub fn get_configs(
+    rpc_client: &RpcClient,
+    ids: &[u64],
+) -> Result<Vec<Option<Config>>, ClientError> {
+    BatchLoader::new(rpc_client).get_configs(ids)
+}
+
+/// Fetches the mainnet `Profile` of every (id, user), see [`BatchLoader::get_profiles`].
+pub fn get_profiles(
+    rpc_client: &RpcClient,
+    profiles: &[(u64, Pubkey)],
+) -> Result<Vec<Option<Profile>>, ClientError> {
+    BatchLoader::new(rpc_client).get_profiles(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::{
+        rpc_client::Mocks,
+        rpc_request::RpcRequest,
+        rpc_response::{Response, RpcResponseContext},
+    };
+
+    #[test]
+    fn test_get_configs() {
+        let config = Config {
+            id: 42,
+            supply: 1_000_000_000,
+            ..Config::default()
+        };
+        let account = Account {
+            lamports: 1_392_000,
+            data: config.try_to_vec().unwrap(),
+            owner: crate::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let (address, _) = FriendzyProgram::MAINNET.derive_config_address(42);
+        let value = Response {
+            context: RpcResponseContext {
+                slot: 1,
+                api_version: None,
+            },
+            value: vec![
+                Some(UiAccount::encode(
+                    &address,
+                    &account,
+                    UiAccountEncoding::Base64,
+                    None,
+                    None,
+                )),
+                None,
+            ],
+        };
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            serde_json::to_value(value).unwrap(),
+        );
+        let rpc_client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+        let configs = BatchLoader::new(&rpc_client)
+            .with_concurrency(0)
+            .get_configs(&[42, 7])
+            .unwrap();
+        assert_eq!(vec![Some(config), None], configs);
+
+        // a missing account is `None`, an undecodable one is an error
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let accounts = vec![None, Some(Account::default())];
+        assert!(matches!(
+            decode_accounts::<Profile>(&addresses, accounts),
+            Err(ClientError::Undecodable(address)) if address == addresses[1]
+        ));
+        assert_eq!(
+            vec![None::<Profile>],
+            decode_accounts(&addresses[..1], vec![None]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use std::collections::HashMap;
+
+use crate::{cache::FriendzyAddresses, program::FriendzyProgram, state::*};
+
+/// The kind of Friendzy account an address refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountKind {
+    Mint,
+    Config,
+    Profile,
+    Metadata,
+}
+
+/// What is known about an address: the kind of account and the X id it belongs to.
+///
+/// The owner is the user of a `Profile`, or the owner of a `Config` when recovered from its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountLabel {
+    pub kind: AccountKind,
+    pub id: u64,
+    pub owner: Option<Pubkey>,
+}
+
+/// Maps derived addresses back to the id and owner they were derived from.
+#[derive(Debug, Default, Clone)]
+pub struct ReverseIndex {
+    program: FriendzyProgram,
+    labels: HashMap<Pubkey, AccountLabel>,
+}
+
+impl ReverseIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_program(program: FriendzyProgram) -> Self {
+        Self {
+            program,
+            ..Default::default()
+        }
+    }
+
+    /// Indexes the mint, config and metadata addresses of the id.
+    pub fn insert_id(&mut self, id: u64) {
+        self.insert_addresses(&FriendzyAddresses::for_program(&self.program, id));
+    }
+
+    pub fn insert_addresses(&mut self, addresses: &FriendzyAddresses) {
+        let id = addresses.id;
+        for (address, kind) in [
+            (addresses.mint, AccountKind::Mint),
+            (addresses.config, AccountKind::Config),
+            (addresses.metadata, AccountKind::Metadata),
+        ] {
+            self.labels.insert(
+                address,
+                AccountLabel {
+                    kind,
+                    id,
+                    owner: None,
+                },
+            );
+        }
+    }
+
+    /// Indexes the profile address of the user for the id.
+    pub fn insert_profile(&mut self, id: u64, user: &Pubkey) {
+        let (profile, _) = self.program.derive_profile_address(id, user);
+        self.labels.insert(
+            profile,
+            AccountLabel {
+                kind: AccountKind::Profile,
+                id,
+                owner: Some(*user),
+            },
+        );
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<&AccountLabel> {
+        self.labels.get(address)
+    }
+
+    /// Returns the id and, for profiles, the user the address was derived from.
+    pub fn resolve(&self, address: &Pubkey) -> Option<(u64, Option<Pubkey>)> {
+        self.get(address).map(|label| (label.id, label.owner))
+    }
+
+    /// Labels each of the given account keys, e.g. the account keys of a transaction message.
+    pub fn label_accounts(&self, keys: &[Pubkey]) -> Vec<Option<AccountLabel>> {
+        keys.iter().map(|key| self.get(key).copied()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+/// Recovers the label of a `Config` or `Profile` account from its data, telling them apart by length.
+pub fn label_account_data(data: &[u8]) -> Option<AccountLabel> {
+    match data.len() {
+        Config::LEN => Config::try_from_slice(data)
+            .ok()
+            .map(|config| AccountLabel {
+                kind: AccountKind::Config,
+                id: config.id,
+                owner: Some(config.owner),
+            }),
+        Profile::LEN => Profile::try_from_slice(data)
+            .ok()
+            .map(|profile| AccountLabel {
+                kind: AccountKind::Profile,
+                id: profile.id,
+                owner: Some(profile.owner),
+            }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose, Engine};
+    use std::str::FromStr;
+
+    fn decode_base64(data: &str) -> Vec<u8> {
+        general_purpose::STANDARD.decode(data).unwrap()
+    }
+
+    #[test]
+    fn test_reverse_index() -> Result<()> {
+        // https://solscan.io/tx/xuNbLveFGhwknY12fbnrRhCffaj355LucVEZXoudjxhESB1BVi27Z2bPrqQcZirSHktkCSM3dVqHWrojB8ZyzQk
+        let id = 1_162_302_698_118_684_672;
+        let user = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+
+        let mut index = ReverseIndex::new();
+        index.insert_id(id);
+        index.insert_profile(id, &user);
+        assert_eq!(4, index.len());
+
+        let config = Pubkey::from_str("5Y3ac7p37XtSMmbb84QuP8dZTv3tfuH2pu1Wg6dokwDQ").unwrap();
+        let profile = Pubkey::from_str("74zFAk5CPA9SNmJPD2K7DqS8WMP1cG9G7DbKN1vyYiVd").unwrap();
+        let mint = Pubkey::from_str("GUZJcmy4QRF3dXWcRxueyPGQfQAmRs1FtqiszRJaFfxV").unwrap();
+
+        assert_eq!(Some((id, None)), index.resolve(&config));
+        assert_eq!(Some((id, Some(user))), index.resolve(&profile));
+        assert_eq!(AccountKind::Mint, index.get(&mint).unwrap().kind);
+        assert_eq!(None, index.resolve(&user));
+
+        let labels = index.label_accounts(&[user, config]);
+        assert_eq!(None, labels[0]);
+        assert_eq!(AccountKind::Config, labels[1].unwrap().kind);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_account_data() -> Result<()> {
+        let owner = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+
+        let data = decode_base64("AKDXUiVUIRAALr5DLwAAAApz5x/t0hNl7QruhPzk4rIGR/001ey9oRXwI9JjP4d4QBxARgAAAAApndAJAAAAAAAAAAAAAAAA");
+        let label = label_account_data(&data).unwrap();
+        assert_eq!(AccountKind::Config, label.kind);
+        assert_eq!(1_162_302_698_118_684_672, label.id);
+        assert_eq!(Some(owner), label.owner);
+
+        let data = decode_base64("AKDXUiVUIRAKc+cf7dITZe0K7oT85OKyBkf9NNXsvaEV8CPSYz+HeAC4mj4KAAAAAAAAAAAAAADLyIBbAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        let label = label_account_data(&data).unwrap();
+        assert_eq!(AccountKind::Profile, label.kind);
+        assert_eq!(1_162_302_698_118_684_672, label.id);
+        assert_eq!(Some(owner), label.owner);
+
+        assert_eq!(None, label_account_data(&data[..40]));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,848 @@
+use anchor_lang::AnchorDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, signature::Keypair,
+    signature::Signature, signer::Signer,
+};
+use std::{
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    calculate_buy_cost,
+    error::ClientError,
+    fees::FeeSchedule,
+    fees::BPS_DENOMINATOR,
+    program::FriendzyProgram,
+    transaction::{check_confirmation, Confirmation, TransactionBuilder},
+    Config, Side, DECIMAL_DENOMINATOR,
+};
+
+/// When an order fires, compared to the curve cost of the next whole key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderKind {
+    /// Buys once the price is at or below the trigger.
+    LimitBuy,
+    /// Sells once the price is at or above the trigger.
+    TakeProfit,
+    /// Sells once the price is at or below the trigger.
+    StopLoss,
+}
+
+impl OrderKind {
+    pub fn side(&self) -> Side {
+        match self {
+            OrderKind::LimitBuy => Side::Buy,
+            OrderKind::TakeProfit | OrderKind::StopLoss => Side::Sell,
+        }
+    }
+
+    pub fn is_triggered(&self, price: u64, trigger_price: u64) -> bool {
+        match self {
+            OrderKind::LimitBuy | OrderKind::StopLoss => price <= trigger_price,
+            OrderKind::TakeProfit => price >= trigger_price,
+        }
+    }
+}
+
+impl fmt::Display for OrderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderKind::LimitBuy => write!(f, "limit_buy"),
+            OrderKind::TakeProfit => write!(f, "take_profit"),
+            OrderKind::StopLoss => write!(f, "stop_loss"),
+        }
+    }
+}
+
+impl FromStr for OrderKind {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "limit_buy" => Ok(OrderKind::LimitBuy),
+            "take_profit" => Ok(OrderKind::TakeProfit),
+            "stop_loss" => Ok(OrderKind::StopLoss),
+            kind => Err(invalid_data(format!("unknown order kind {}", kind))),
+        }
+    }
+}
+
+/// A swap that fires when the price of the id crosses the trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerOrder {
+    pub order_id: u64,
+    pub id: u64,
+    pub kind: OrderKind,
+    /// The amount of keys in native units.
+    pub amount: u64,
+    /// The curve cost of the next whole key in lamports.
+    pub trigger_price: u64,
+    /// The slippage allowed on the quote when setting the price bound of the swap.
+    pub slippage_bps: u64,
+}
+
+impl TriggerOrder {
+    /// Returns the `max_price` or `min_price` of the swap at the given supply, `None` when the
+    /// curve can't quote it, e.g. selling more than the supply.
+    pub fn price_bound(&self, fees: &FeeSchedule, supply: u64) -> Option<u64> {
+        let quote = fees.quote(self.kind.side(), supply, self.amount)?;
+        let total = quote.total() as u128;
+        let bound = match self.kind.side() {
+            Side::Sell => total * (BPS_DENOMINATOR.saturating_sub(self.slippage_bps)) as u128,
+            _ => total * (BPS_DENOMINATOR + self.slippage_bps) as u128,
+        } / BPS_DENOMINATOR as u128;
+        u64::try_from(bound).ok()
+    }
+}
+
+/// A fired order whose swap was sent but hasn't landed yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InFlightOrder {
+    pub order: TriggerOrder,
+    pub signature: Signature,
+    /// The last block height the swap's blockhash is valid at, it can't land after it.
+    pub last_valid_block_height: u64,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// The pending trigger orders and the orders in flight, persisted as CSV.
+///
+/// The CSV starts with the next order id, so ids of cancelled or filled orders are never reused.
+/// Orders in flight have the signature and last valid block height of their swap in the last two
+/// columns, pending orders leave them empty.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrderBook {
+    orders: Vec<TriggerOrder>,
+    in_flight: Vec<InFlightOrder>,
+    next_order_id: u64,
+}
+
+impl OrderBook {
+    const HEADER: &'static str =
+        "order_id,id,kind,amount,trigger_price,slippage_bps,signature,last_valid_block_height";
+    const NEXT_ORDER_ID: &'static str = "next_order_id";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places an order and returns its order id.
+    pub fn place(
+        &mut self,
+        id: u64,
+        kind: OrderKind,
+        amount: u64,
+        trigger_price: u64,
+        slippage_bps: u64,
+    ) -> u64 {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        self.orders.push(TriggerOrder {
+            order_id,
+            id,
+            kind,
+            amount,
+            trigger_price,
+            slippage_bps,
+        });
+        order_id
+    }
+
+    pub fn cancel(&mut self, order_id: u64) -> Option<TriggerOrder> {
+        let index = self.orders.iter().position(|o| o.order_id == order_id)?;
+        Some(self.orders.remove(index))
+    }
+
+    pub fn orders(&self) -> &[TriggerOrder] {
+        &self.orders
+    }
+
+    pub fn in_flight(&self) -> &[InFlightOrder] {
+        &self.in_flight
+    }
+
+    /// Returns the ids with pending orders.
+    pub fn ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.orders.iter().map(|o| o.id).collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Returns the orders of the id triggered at the given supply.
+    pub fn triggered(&self, id: u64, supply: u64) -> Vec<TriggerOrder> {
+        let Some(price) = calculate_buy_cost(supply, DECIMAL_DENOMINATOR) else {
+            return vec![];
+        };
+        self.orders
+            .iter()
+            .filter(|o| o.id == id && o.kind.is_triggered(price, o.trigger_price))
+            .copied()
+            .collect()
+    }
+
+    /// Removes and returns the orders of the id triggered at the given supply.
+    pub fn take_triggered(&mut self, id: u64, supply: u64) -> Vec<TriggerOrder> {
+        let Some(price) = calculate_buy_cost(supply, DECIMAL_DENOMINATOR) else {
+            return vec![];
+        };
+        let (triggered, pending) = self
+            .orders
+            .iter()
+            .partition(|o| o.id == id && o.kind.is_triggered(price, o.trigger_price));
+        self.orders = pending;
+        triggered
+    }
+
+    /// Puts back an order, e.g. when sending its swap failed.
+    pub fn restore(&mut self, order: TriggerOrder) {
+        self.next_order_id = self.next_order_id.max(order.order_id + 1);
+        let index = self.orders.partition_point(|o| o.order_id < order.order_id);
+        self.orders.insert(index, order);
+    }
+
+    /// Moves a fired order to the orders in flight, its swap is about to be sent.
+    pub fn send(&mut self, in_flight: InFlightOrder) {
+        self.cancel(in_flight.order.order_id);
+        self.next_order_id = self.next_order_id.max(in_flight.order.order_id + 1);
+        self.in_flight.push(in_flight);
+    }
+
+    /// Removes an order from the orders in flight once its swap landed or expired, the order is
+    /// put back unless it was filled.
+    pub fn settle(&mut self, order_id: u64, filled: bool) -> Option<InFlightOrder> {
+        let index = self
+            .in_flight
+            .iter()
+            .position(|o| o.order.order_id == order_id)?;
+        let in_flight = self.in_flight.remove(index);
+        if !filled {
+            self.restore(in_flight.order);
+        }
+        Some(in_flight)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!(
+            "{},{}\n{}\n",
+            Self::NEXT_ORDER_ID,
+            self.next_order_id,
+            Self::HEADER
+        );
+        let pending = self
+            .orders
+            .iter()
+            .map(|o| (o, String::new(), String::new()));
+        let in_flight = self.in_flight.iter().map(|o| {
+            (
+                &o.order,
+                o.signature.to_string(),
+                o.last_valid_block_height.to_string(),
+            )
+        });
+        for (o, signature, last_valid_block_height) in pending.chain(in_flight) {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                o.order_id,
+                o.id,
+                o.kind,
+                o.amount,
+                o.trigger_price,
+                o.slippage_bps,
+                signature,
+                last_valid_block_height
+            ));
+        }
+        csv
+    }
+
+    /// Parses the CSV, without the next order id line ids continue after the highest order.
+    ///
+    /// Rows without the last two columns, saved before orders were tracked in flight, are pending.
+    pub fn from_csv(csv: &str) -> io::Result<Self> {
+        let mut book = Self::new();
+        let mut lines = csv.lines().peekable();
+
+        let prefix = format!("{},", Self::NEXT_ORDER_ID);
+        if let Some(next_order_id) = lines.peek().and_then(|line| line.strip_prefix(&prefix)) {
+            book.next_order_id = next_order_id.trim().parse().map_err(|e| {
+                invalid_data(format!("invalid next order id {}: {}", next_order_id, e))
+            })?;
+            lines.next();
+        }
+
+        for line in lines.skip(1).filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let (fields, in_flight) = fields.split_at(fields.len().min(6));
+            let [order_id, id, kind, amount, trigger_price, slippage_bps] = fields[..] else {
+                return Err(invalid_data(format!("invalid order {}", line)));
+            };
+            let number = |field: &str| {
+                field
+                    .parse::<u64>()
+                    .map_err(|e| invalid_data(format!("invalid order {}: {}", line, e)))
+            };
+
+            let order = TriggerOrder {
+                order_id: number(order_id)?,
+                id: number(id)?,
+                kind: kind.parse()?,
+                amount: number(amount)?,
+                trigger_price: number(trigger_price)?,
+                slippage_bps: number(slippage_bps)?,
+            };
+            match in_flight {
+                [] | ["", ""] => book.restore(order),
+                [signature, last_valid_block_height] => book.send(InFlightOrder {
+                    order,
+                    signature: signature
+                        .parse()
+                        .map_err(|e| invalid_data(format!("invalid order {}: {}", line, e)))?,
+                    last_valid_block_height: number(last_valid_block_height)?,
+                }),
+                _ => return Err(invalid_data(format!("invalid order {}", line))),
+            }
+        }
+
+        Ok(book)
+    }
+
+    /// Loads the book, an empty one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(csv) => Self::from_csv(&csv),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saves the book, writing to a temporary file first so a crash can't truncate it.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.to_csv())?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// A fired order and the signature of its swap.
+pub type OrderResult = (TriggerOrder, Result<Signature, ClientError>);
+
+/// An order that left the orders in flight and how its swap ended.
+pub type SettledOrder = (InFlightOrder, Confirmation);
+
+/// What happened to the orders while running.
+#[derive(Debug)]
+pub enum OrderEvent {
+    Fired(OrderResult),
+    Settled(SettledOrder),
+}
+
+/// Fires the orders of a persisted [`OrderBook`] as `Config` accounts change.
+///
+/// A fired order is saved as in flight with the signature of its swap before the swap is sent, so
+/// a restart never sends it twice. It stays in flight until [`OrderExecutor::settle`] finds the
+/// swap landed, and is put back if the swap failed or its blockhash expired. That includes swaps
+/// whose send returned an error, they are only known not to have landed once they expire.
+pub struct OrderExecutor {
+    program: FriendzyProgram,
+    rpc_client: RpcClient,
+    payer: Keypair,
+    fees: FeeSchedule,
+    compute_unit_price: u64,
+    book: OrderBook,
+    path: PathBuf,
+}
+
+impl OrderExecutor {
+    /// Loads the book at the path, every change to the book is saved back to it.
+    pub fn new(
+        program: FriendzyProgram,
+        rpc_client: RpcClient,
+        payer: Keypair,
+        fees: FeeSchedule,
+        path: PathBuf,
+    ) -> Result<Self, ClientError> {
+        let book = OrderBook::load(&path)?;
+        Ok(Self {
+            program,
+            rpc_client,
+            payer,
+            fees,
+            compute_unit_price: 0,
+            book,
+            path,
+        })
+    }
+
+    pub fn with_compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = micro_lamports;
+        self
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn place(
+        &mut self,
+        id: u64,
+        kind: OrderKind,
+        amount: u64,
+        trigger_price: u64,
+        slippage_bps: u64,
+    ) -> Result<u64, ClientError> {
+        let order_id = self
+            .book
+            .place(id, kind, amount, trigger_price, slippage_bps);
+        self.book.save(&self.path)?;
+        Ok(order_id)
+    }
+
+    pub fn cancel(&mut self, order_id: u64) -> Result<Option<TriggerOrder>, ClientError> {
+        let order = self.book.cancel(order_id);
+        self.book.save(&self.path)?;
+        Ok(order)
+    }
+
+    /// Sends the swaps of the orders triggered by the config, the book is saved before each send.
+    /// Orders whose swap couldn't be built or saved stay pending.
+    pub fn handle_config(&mut self, config: &Config) -> Vec<OrderResult> {
+        self.book
+            .triggered(config.id, config.supply)
+            .into_iter()
+            .map(|order| (order, self.send(order, config.supply)))
+            .collect()
+    }
+
+    /// Checks the swaps of the orders in flight, landed orders are dropped and orders whose swap
+    /// failed or expired are put back. Orders still in flight aren't returned.
+    pub fn settle(&mut self) -> Result<Vec<SettledOrder>, ClientError> {
+        let mut settled = vec![];
+        for in_flight in self.book.in_flight().to_vec() {
+            let confirmation = check_confirmation(
+                &self.rpc_client,
+                &in_flight.signature,
+                in_flight.last_valid_block_height,
+            )?;
+            if confirmation == Confirmation::Pending {
+                continue;
+            }
+            let filled = confirmation == Confirmation::Succeeded;
+            self.book.settle(in_flight.order.order_id, filled);
+            settled.push((in_flight, confirmation));
+        }
+
+        if !settled.is_empty() {
+            self.book.save(&self.path)?;
+        }
+        Ok(settled)
+    }
+
+    fn send(&mut self, order: TriggerOrder, supply: u64) -> Result<Signature, ClientError> {
+        let price = order
+            .price_bound(&self.fees, supply)
+            .ok_or(ClientError::Unquotable(order.id))?;
+        let (blockhash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?;
+        let transaction = TransactionBuilder::new(self.payer.pubkey())
+            .compute_unit_price(self.compute_unit_price)
+            .blockhash(blockhash)
+            .swap(
+                &self.program,
+                order.id,
+                order.amount,
+                price,
+                order.kind.side(),
+                false,
+            )
+            .build(&[&self.payer])?;
+        let signature = transaction.signatures[0];
+
+        self.book.send(InFlightOrder {
+            order,
+            signature,
+            last_valid_block_height,
+        });
+        if let Err(e) = self.book.save(&self.path) {
+            self.book.settle(order.order_id, false);
+            return Err(e.into());
+        }
+
+        self.rpc_client.send_transaction(&transaction)?;
+        Ok(signature)
+    }
+
+    /// Subscribes to the program's `Config` accounts and fires orders until the subscription
+    /// closes, the orders in flight are settled on every update.
+    ///
+    /// A failed save is reported, a fired order whose save failed isn't sent and stays pending.
+    pub fn run<F>(&mut self, websocket_url: &str, mut on_event: F) -> Result<(), ClientError>
+    where
+        F: FnMut(Result<OrderEvent, ClientError>),
+    {
+        let (_subscription, receiver) = PubsubClient::program_subscribe(
+            websocket_url,
+            &self.program.program_id,
+            Some(RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::DataSize(Config::LEN as u64)]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            }),
+        )?;
+
+        for response in receiver {
+            let Some(account) = response.value.account.decode::<Account>() else {
+                continue;
+            };
+            let Ok(config) = Config::try_from_slice(&account.data) else {
+                continue;
+            };
+
+            match self.settle() {
+                Ok(settled) => settled
+                    .into_iter()
+                    .for_each(|settled| on_event(Ok(OrderEvent::Settled(settled)))),
+                Err(e) => on_event(Err(e)),
+            }
+
+            self.handle_config(&config)
+                .into_iter()
+                .for_each(|result| on_event(Ok(OrderEvent::Fired(result))));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use base64::{engine::general_purpose, Engine};
+    use serde_json::Value;
+    use solana_client::{
+        client_error::{ClientErrorKind, Result as RpcResult},
+        rpc_client::RpcClientConfig,
+        rpc_request::RpcRequest,
+        rpc_response::{Response, RpcBlockhash, RpcResponseContext, RpcVersionInfo},
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+    use solana_sdk::{
+        hash::Hash,
+        instruction::InstructionError,
+        transaction::{TransactionError, VersionedTransaction},
+    };
+    use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    };
+
+    /// An RPC landing sent transactions with the `status`, sends fail while `reject` is set and
+    /// blockhashes are valid until block height 1.
+    #[derive(Default, Clone)]
+    struct StubRpc {
+        sent: Arc<Mutex<Vec<Signature>>>,
+        status: Arc<Mutex<Option<Result<(), TransactionError>>>>,
+        block_height: Arc<AtomicU64>,
+        reject: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl RpcSender for StubRpc {
+        async fn send(&self, request: RpcRequest, params: Value) -> RpcResult<Value> {
+            let context = RpcResponseContext {
+                slot: 1,
+                api_version: None,
+            };
+            let value = match request {
+                RpcRequest::GetLatestBlockhash => serde_json::to_value(Response {
+                    context,
+                    value: RpcBlockhash {
+                        blockhash: Hash::new_unique().to_string(),
+                        last_valid_block_height: 1,
+                    },
+                }),
+                RpcRequest::SendTransaction => {
+                    if self.reject.load(Ordering::SeqCst) {
+                        return Err(ClientErrorKind::Custom("rejected".to_string()).into());
+                    }
+                    let data = general_purpose::STANDARD
+                        .decode(params[0].as_str().unwrap())
+                        .unwrap();
+                    let transaction: VersionedTransaction = bincode::deserialize(&data).unwrap();
+                    self.sent.lock().unwrap().push(transaction.signatures[0]);
+                    serde_json::to_value(transaction.signatures[0].to_string())
+                }
+                RpcRequest::GetBlockHeight => {
+                    serde_json::to_value(self.block_height.load(Ordering::SeqCst))
+                }
+                RpcRequest::GetSignatureStatuses => {
+                    let value =
+                        self.status
+                            .lock()
+                            .unwrap()
+                            .clone()
+                            .map(|status| TransactionStatus {
+                                slot: 1,
+                                confirmations: Some(1),
+                                err: status.clone().err(),
+                                status,
+                                confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+                            });
+                    serde_json::to_value(Response {
+                        context,
+                        value: vec![value],
+                    })
+                }
+                RpcRequest::GetVersion => serde_json::to_value(RpcVersionInfo {
+                    solana_core: "1.16.27".to_string(),
+                    feature_set: None,
+                }),
+                request => panic!("unexpected request {}", request),
+            };
+            Ok(value?)
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "stub".to_string()
+        }
+    }
+
+    #[test]
+    fn test_triggers() {
+        let mut book = OrderBook::new();
+        let price = calculate_buy_cost(100 * DECIMAL_DENOMINATOR, DECIMAL_DENOMINATOR).unwrap();
+
+        let buy = book.place(42, OrderKind::LimitBuy, DECIMAL_DENOMINATOR, price, 100);
+        let take_profit = book.place(42, OrderKind::TakeProfit, DECIMAL_DENOMINATOR, price * 2, 0);
+        let stop_loss = book.place(42, OrderKind::StopLoss, DECIMAL_DENOMINATOR, price / 2, 0);
+        book.place(7, OrderKind::LimitBuy, DECIMAL_DENOMINATOR, u64::MAX, 0);
+
+        assert!(book
+            .take_triggered(42, 101 * DECIMAL_DENOMINATOR)
+            .is_empty());
+
+        let triggered = book.take_triggered(42, 100 * DECIMAL_DENOMINATOR);
+        assert_eq!(
+            vec![buy],
+            triggered.iter().map(|o| o.order_id).collect::<Vec<_>>()
+        );
+
+        let triggered = book.take_triggered(42, 1_000 * DECIMAL_DENOMINATOR);
+        assert_eq!(
+            vec![take_profit],
+            triggered.iter().map(|o| o.order_id).collect::<Vec<_>>()
+        );
+
+        let triggered = book.take_triggered(42, 0);
+        assert_eq!(
+            vec![stop_loss],
+            triggered.iter().map(|o| o.order_id).collect::<Vec<_>>()
+        );
+
+        assert_eq!(vec![7], book.ids());
+    }
+
+    #[test]
+    fn test_price_bound() {
+        let fees = FeeSchedule {
+            royalty_bps: 500,
+            protocol_bps: 500,
+        };
+        let buy = TriggerOrder {
+            order_id: 0,
+            id: 42,
+            kind: OrderKind::LimitBuy,
+            amount: DECIMAL_DENOMINATOR,
+            trigger_price: 0,
+            slippage_bps: 100,
+        };
+        // 10_000_000 curve cost plus 10% fees plus 1% slippage
+        assert_eq!(Some(11_110_000), buy.price_bound(&fees, 0));
+
+        let sell = TriggerOrder {
+            kind: OrderKind::StopLoss,
+            ..buy
+        };
+        assert_eq!(
+            Some(8_910_000),
+            sell.price_bound(&fees, DECIMAL_DENOMINATOR)
+        );
+        assert_eq!(None, sell.price_bound(&fees, 0));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut book = OrderBook::new();
+        book.place(42, OrderKind::LimitBuy, 1, 2, 3);
+        let cancelled = book.place(42, OrderKind::StopLoss, 4, 5, 6);
+        book.place(1_436_880_221_354_045_450, OrderKind::TakeProfit, 7, 8, 9);
+        book.cancel(cancelled);
+
+        let path = std::env::temp_dir().join(format!("friendzy-orders-{}.csv", std::process::id()));
+        book.save(&path).unwrap();
+        let mut loaded = OrderBook::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(book.orders(), loaded.orders());
+        assert_eq!(3, loaded.place(42, OrderKind::LimitBuy, 1, 2, 3));
+
+        // cancelling the highest order doesn't free its id
+        let mut book = OrderBook::new();
+        book.place(42, OrderKind::LimitBuy, 1, 2, 3);
+        let highest = book.place(42, OrderKind::StopLoss, 4, 5, 6);
+        book.cancel(highest);
+        let mut loaded = OrderBook::from_csv(&book.to_csv()).unwrap();
+        assert_eq!(highest + 1, loaded.place(42, OrderKind::LimitBuy, 1, 2, 3));
+
+        // books saved without the next order id continue after the highest pending one
+        let csv = format!("{}\n7,42,limit_buy,1,2,3\n", OrderBook::HEADER);
+        assert_eq!(
+            8,
+            OrderBook::from_csv(&csv)
+                .unwrap()
+                .place(42, OrderKind::LimitBuy, 1, 2, 3)
+        );
+
+        assert!(OrderBook::from_csv("order_id\n1,2,limit_buy").is_err());
+        assert!(OrderBook::from_csv("order_id\n1,2,market,3,4,5").is_err());
+        assert!(OrderBook::from_csv("next_order_id,x\norder_id\n").is_err());
+    }
+
+    #[test]
+    fn test_in_flight_persistence() {
+        let mut book = OrderBook::new();
+        let first = book.place(42, OrderKind::LimitBuy, 1, 2, 3);
+        let second = book.place(42, OrderKind::StopLoss, 4, 5, 6);
+        let third = book.place(43, OrderKind::TakeProfit, 7, 8, 9);
+
+        let order = book.orders()[1];
+        let in_flight = InFlightOrder {
+            order,
+            signature: Signature::new_unique(),
+            last_valid_block_height: 100,
+        };
+        book.send(in_flight);
+        assert_eq!(
+            vec![first, third],
+            book.orders().iter().map(|o| o.order_id).collect::<Vec<_>>()
+        );
+
+        let loaded = OrderBook::from_csv(&book.to_csv()).unwrap();
+        assert_eq!(book, loaded);
+        assert_eq!(&[in_flight], loaded.in_flight());
+
+        // a failed swap puts the order back in order id order
+        let mut failed = loaded.clone();
+        assert_eq!(Some(in_flight), failed.settle(second, false));
+        assert_eq!(
+            vec![first, second, third],
+            failed
+                .orders()
+                .iter()
+                .map(|o| o.order_id)
+                .collect::<Vec<_>>()
+        );
+        assert!(failed.in_flight().is_empty());
+
+        let mut filled = loaded;
+        assert_eq!(Some(in_flight), filled.settle(second, true));
+        assert_eq!(None, filled.settle(second, true));
+        assert_eq!(
+            vec![first, third],
+            filled
+                .orders()
+                .iter()
+                .map(|o| o.order_id)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(OrderBook::from_csv("order_id\n1,2,limit_buy,3,4,5,x,6").is_err());
+    }
+
+    #[test]
+    fn test_executor_settles_in_flight() {
+        let rpc = StubRpc::default();
+        let path =
+            std::env::temp_dir().join(format!("friendzy-executor-{}.csv", std::process::id()));
+        let mut executor = OrderExecutor::new(
+            FriendzyProgram::MAINNET,
+            RpcClient::new_sender(rpc.clone(), RpcClientConfig::default()),
+            Keypair::new(),
+            FeeSchedule {
+                royalty_bps: 500,
+                protocol_bps: 500,
+            },
+            path.clone(),
+        )
+        .unwrap();
+        let order_id = executor
+            .place(42, OrderKind::LimitBuy, DECIMAL_DENOMINATOR, u64::MAX, 100)
+            .unwrap();
+        let config = Config {
+            id: 42,
+            ..Config::default()
+        };
+
+        // the order is saved in flight with the signature of its swap
+        let results = executor.handle_config(&config);
+        let signature = *results[0].1.as_ref().unwrap();
+        assert_eq!(vec![signature], *rpc.sent.lock().unwrap());
+        assert!(executor.book().orders().is_empty());
+        assert_eq!(executor.book(), &OrderBook::load(&path).unwrap());
+        assert!(executor.handle_config(&config).is_empty());
+
+        assert!(executor.settle().unwrap().is_empty());
+        assert_eq!(1, executor.book().in_flight().len());
+
+        // a failed swap puts the order back
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(0));
+        *rpc.status.lock().unwrap() = Some(Err(err.clone()));
+        let settled = executor.settle().unwrap();
+        assert_eq!(Confirmation::Failed(err), settled[0].1);
+        assert_eq!(order_id, executor.book().orders()[0].order_id);
+
+        // a rejected send stays in flight until its blockhash expires
+        *rpc.status.lock().unwrap() = None;
+        rpc.reject.store(true, Ordering::SeqCst);
+        assert!(executor.handle_config(&config)[0].1.is_err());
+        assert_eq!(1, executor.book().in_flight().len());
+        rpc.block_height.store(2, Ordering::SeqCst);
+        assert_eq!(Confirmation::Expired, executor.settle().unwrap()[0].1);
+        assert_eq!(1, executor.book().orders().len());
+
+        // a landed swap fills the order
+        rpc.reject.store(false, Ordering::SeqCst);
+        rpc.block_height.store(0, Ordering::SeqCst);
+        executor.handle_config(&config);
+        *rpc.status.lock().unwrap() = Some(Ok(()));
+        assert_eq!(Confirmation::Succeeded, executor.settle().unwrap()[0].1);
+        assert_eq!(OrderBook::load(&path).unwrap(), *executor.book());
+        fs::remove_file(&path).unwrap();
+        assert!(executor.book().orders().is_empty());
+        assert!(executor.book().in_flight().is_empty());
+    }
+}
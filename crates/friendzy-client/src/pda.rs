@@ -1,36 +1,121 @@
 use anchor_lang::prelude::*;
 
+use crate::program::FriendzyProgram;
+
+impl FriendzyProgram {
+    pub fn derive_bank_address(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&["bank".as_ref()], &self.program_id)
+    }
+
+    pub fn derive_mint_address(&self, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &["mint".as_ref(), id.to_le_bytes().as_ref()],
+            &self.program_id,
+        )
+    }
+
+    pub fn derive_profile_address(&self, id: u64, user: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &["config".as_ref(), id.to_le_bytes().as_ref(), user.as_ref()],
+            &self.program_id,
+        )
+    }
+
+    pub fn derive_config_address(&self, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &["config".as_ref(), id.to_le_bytes().as_ref()],
+            &self.program_id,
+        )
+    }
+
+    pub fn derive_metadata_address(&self, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                "metadata".as_ref(),
+                self.metadata_program.as_ref(),
+                mint.as_ref(),
+            ],
+            &self.metadata_program,
+        )
+    }
+
+    pub fn create_mint_address(&self, id: u64, bump: u8) -> Result<Pubkey> {
+        Ok(Pubkey::create_program_address(
+            &["mint".as_ref(), id.to_le_bytes().as_ref(), &[bump]],
+            &self.program_id,
+        )
+        .map_err(ProgramError::from)?)
+    }
+
+    pub fn create_profile_address(&self, id: u64, user: &Pubkey, bump: u8) -> Result<Pubkey> {
+        Ok(Pubkey::create_program_address(
+            &[
+                "config".as_ref(),
+                id.to_le_bytes().as_ref(),
+                user.as_ref(),
+                &[bump],
+            ],
+            &self.program_id,
+        )
+        .map_err(ProgramError::from)?)
+    }
+
+    pub fn create_config_address(&self, id: u64, bump: u8) -> Result<Pubkey> {
+        Ok(Pubkey::create_program_address(
+            &["config".as_ref(), id.to_le_bytes().as_ref(), &[bump]],
+            &self.program_id,
+        )
+        .map_err(ProgramError::from)?)
+    }
+
+    pub fn create_metadata_address(&self, mint: &Pubkey, bump: u8) -> Result<Pubkey> {
+        Ok(Pubkey::create_program_address(
+            &[
+                "metadata".as_ref(),
+                self.metadata_program.as_ref(),
+                mint.as_ref(),
+                &[bump],
+            ],
+            &self.metadata_program,
+        )
+        .map_err(ProgramError::from)?)
+    }
+}
+
 pub fn derive_bank_address() -> (Pubkey, u8) {
-    Pubkey::find_program_address(&["bank".as_ref()], &crate::id())
+    FriendzyProgram::MAINNET.derive_bank_address()
 }
 
 pub fn derive_mint_address(id: u64) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&["mint".as_ref(), id.to_le_bytes().as_ref()], &crate::id())
+    FriendzyProgram::MAINNET.derive_mint_address(id)
 }
 
 pub fn derive_profile_address(id: u64, user: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(
-        &["config".as_ref(), id.to_le_bytes().as_ref(), user.as_ref()],
-        &crate::id(),
-    )
+    FriendzyProgram::MAINNET.derive_profile_address(id, user)
 }
 
 pub fn derive_config_address(id: u64) -> (Pubkey, u8) {
-    Pubkey::find_program_address(
-        &["config".as_ref(), id.to_le_bytes().as_ref()],
-        &crate::id(),
-    )
+    FriendzyProgram::MAINNET.derive_config_address(id)
 }
 
 pub fn derive_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(
-        &[
-            "metadata".as_ref(),
-            mpl_token_metadata::ID.as_ref(),
-            mint.as_ref(),
-        ],
-        &mpl_token_metadata::ID,
-    )
+    FriendzyProgram::MAINNET.derive_metadata_address(mint)
+}
+
+pub fn create_mint_address(id: u64, bump: u8) -> Result<Pubkey> {
+    FriendzyProgram::MAINNET.create_mint_address(id, bump)
+}
+
+pub fn create_profile_address(id: u64, user: &Pubkey, bump: u8) -> Result<Pubkey> {
+    FriendzyProgram::MAINNET.create_profile_address(id, user, bump)
+}
+
+pub fn create_config_address(id: u64, bump: u8) -> Result<Pubkey> {
+    FriendzyProgram::MAINNET.create_config_address(id, bump)
+}
+
+pub fn create_metadata_address(mint: &Pubkey, bump: u8) -> Result<Pubkey> {
+    FriendzyProgram::MAINNET.create_metadata_address(mint, bump)
 }
 
 #[cfg(test)]
@@ -99,4 +184,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_create_with_known_bumps() -> Result<()> {
+        let id = 1_162_302_698_118_684_672;
+        let user = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+
+        let (mint, mint_bump) = derive_mint_address(id);
+        let (config, config_bump) = derive_config_address(id);
+        let (profile, profile_bump) = derive_profile_address(id, &user);
+        let (metadata, metadata_bump) = derive_metadata_address(&mint);
+
+        assert_eq!(mint, create_mint_address(id, mint_bump)?);
+        assert_eq!(config, create_config_address(id, config_bump)?);
+        assert_eq!(profile, create_profile_address(id, &user, profile_bump)?);
+        assert_eq!(metadata, create_metadata_address(&mint, metadata_bump)?);
+
+        Ok(())
+    }
 }
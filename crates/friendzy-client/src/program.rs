@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+/// The program ids a Friendzy deployment is made of.
+///
+/// Every PDA derivation and instruction builder is available as a method on this type, the free
+/// functions in the crate are shorthands for [`FriendzyProgram::MAINNET`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FriendzyProgram {
+    pub program_id: Pubkey,
+    pub vault: Pubkey,
+    pub metadata_program: Pubkey,
+}
+
+impl FriendzyProgram {
+    pub const MAINNET: Self = Self {
+        program_id: crate::ID,
+        vault: crate::vault::ID,
+        metadata_program: mpl_token_metadata::ID,
+    };
+
+    /// A deployment of the program with its own vault, e.g. on `solana-test-validator`.
+    ///
+    /// The metadata program is the Metaplex one, which can be cloned into a local validator.
+    pub fn new(program_id: Pubkey, vault: Pubkey) -> Self {
+        Self {
+            program_id,
+            vault,
+            metadata_program: mpl_token_metadata::ID,
+        }
+    }
+
+    pub fn with_metadata_program(mut self, metadata_program: Pubkey) -> Self {
+        self.metadata_program = metadata_program;
+        self
+    }
+}
+
+impl Default for FriendzyProgram {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_mainnet() -> Result<()> {
+        let program = FriendzyProgram::default();
+
+        assert_eq!(crate::id(), program.program_id);
+        assert_eq!(crate::vault::id(), program.vault);
+        assert_eq!(mpl_token_metadata::ID, program.metadata_program);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_deployment_derivations() -> Result<()> {
+        let program = FriendzyProgram::new(Pubkey::new_unique(), Pubkey::new_unique());
+        let id = 1_162_302_698_118_684_672;
+
+        let (mainnet_config, _) = FriendzyProgram::MAINNET.derive_config_address(id);
+        let (config, config_bump) = program.derive_config_address(id);
+        assert_ne!(mainnet_config, config);
+        assert_eq!(config, program.create_config_address(id, config_bump)?);
+
+        let (mint, _) = program.derive_mint_address(id);
+        let (metadata, _) = program.derive_metadata_address(&mint);
+        let (mainnet_metadata, _) = FriendzyProgram::MAINNET.derive_metadata_address(&mint);
+        assert_eq!(mainnet_metadata, metadata);
+
+        let user = Pubkey::new_unique();
+        let accounts = program.swap_accounts(id, &user);
+        assert_eq!(config, accounts.config);
+        assert_eq!(metadata, accounts.metadata);
+        let ix = program.swap(
+            &accounts,
+            true,
+            id,
+            1_000_000_000,
+            100_000_000,
+            crate::Side::Buy,
+        );
+        assert_eq!(program.program_id, ix.program_id);
+        assert_eq!(program.metadata_program, ix.accounts[9].pubkey);
+        assert_eq!(program.vault, ix.accounts[11].pubkey);
+
+        let ix = program.withdraw(&user, &accounts.bank, &config, &mint, &accounts.profile, id);
+        assert_eq!(program.program_id, ix.program_id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use anchor_lang::AnchorDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{error::ClientError, program::FriendzyProgram, Config, Profile};
+
+/// The `getProgramAccounts` filters matching accounts of `len` bytes, owned by `owner` when the
+/// owner offset is given.
+fn filters(len: usize, owner: Option<(usize, &Pubkey)>) -> Vec<RpcFilterType> {
+    let mut filters = vec![RpcFilterType::DataSize(len as u64)];
+    if let Some((offset, owner)) = owner {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            offset,
+            owner.as_ref(),
+        )));
+    }
+    filters
+}
+
+/// The accounts of a scan, an account which can't be decoded is a [`ClientError::Undecodable`].
+pub type Scanned<T> = Result<(Pubkey, T), ClientError>;
+
+/// Decodes the accounts lazily.
+fn decode<T: AnchorDeserialize>(
+    accounts: Vec<(Pubkey, Account)>,
+) -> impl Iterator<Item = Scanned<T>> {
+    accounts.into_iter().map(|(address, account)| {
+        T::try_from_slice(&account.data)
+            .map(|decoded| (address, decoded))
+            .map_err(|_| ClientError::Undecodable(address))
+    })
+}
+
+/// `getProgramAccounts` returns every account in one response, the results are not streamed,
+/// only decoded as the iterator advances.
+fn scan<T: AnchorDeserialize>(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    filters: Vec<RpcFilterType>,
+) -> Result<impl Iterator<Item = Scanned<T>>, ClientError> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        &program.program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+    Ok(decode(accounts))
+}
+
+/// Returns every `Config` of the program with its address.
+///
+/// Like every scan, the whole `getProgramAccounts` response is loaded before the first result,
+/// and some RPC providers don't allow it.
+pub fn scan_configs(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+) -> Result<impl Iterator<Item = Scanned<Config>>, ClientError> {
+    scan(rpc_client, program, filters(Config::LEN, None))
+}
+
+/// Returns every `Config` owned by `owner`, the ids verified to that wallet.
+pub fn scan_configs_by_owner(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    owner: &Pubkey,
+) -> Result<impl Iterator<Item = Scanned<Config>>, ClientError> {
+    scan(
+        rpc_client,
+        program,
+        filters(Config::LEN, Some((Config::OWNER_OFFSET, owner))),
+    )
+}
+
+/// Returns every `Profile` of the program with its address.
+pub fn scan_profiles(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+) -> Result<impl Iterator<Item = Scanned<Profile>>, ClientError> {
+    scan(rpc_client, program, filters(Profile::LEN, None))
+}
+
+/// Returns every `Profile` of `owner`, one per id the wallet traded.
+pub fn scan_profiles_by_owner(
+    rpc_client: &RpcClient,
+    program: &FriendzyProgram,
+    owner: &Pubkey,
+) -> Result<impl Iterator<Item = Scanned<Profile>>, ClientError> {
+    scan(
+        rpc_client,
+        program,
+        filters(Profile::LEN, Some((Profile::OWNER_OFFSET, owner))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+
+    #[test]
+    fn test_filters() {
+        let owner = Pubkey::new_unique();
+        let config = Config {
+            owner,
+            ..Config::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let profile = Profile {
+            owner,
+            ..Profile::default()
+        }
+        .try_to_vec()
+        .unwrap();
+
+        for (len, offset, data) in [
+            (Config::LEN, Config::OWNER_OFFSET, &config),
+            (Profile::LEN, Profile::OWNER_OFFSET, &profile),
+        ] {
+            let filters = filters(len, Some((offset, &owner)));
+            assert_eq!(2, filters.len());
+            for filter in filters {
+                match filter {
+                    RpcFilterType::DataSize(size) => assert_eq!(data.len() as u64, size),
+                    RpcFilterType::Memcmp(memcmp) => assert!(memcmp.bytes_match(data)),
+                    filter => panic!("unexpected filter {:?}", filter),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_reports_invalid_accounts() {
+        let config = Config {
+            id: 42,
+            ..Config::default()
+        };
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = vec![
+            (
+                a,
+                Account {
+                    data: config.try_to_vec().unwrap(),
+                    ..Account::default()
+                },
+            ),
+            (b, Account::default()),
+        ];
+
+        let decoded: Vec<Scanned<Config>> = decode(accounts).collect();
+        assert_eq!((a, config), *decoded[0].as_ref().unwrap());
+        assert!(matches!(decoded[1], Err(ClientError::Undecodable(address)) if address == b));
+    }
+}
@@ -0,0 +1,658 @@
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{
+        RpcSendTransactionConfig, RpcTransactionConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{collections::HashSet, str::FromStr};
+
+use crate::{
+    error::ClientError,
+    program::FriendzyProgram,
+    transaction::{confirm_transaction, Confirmation, TransactionBuilder},
+    VerifyArgs, VERIFY_DISCRIMINATOR,
+};
+
+/// How much to buy when a profile verifies and how to land it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnipeConfig {
+    /// The amount of keys to buy in native units.
+    pub amount: u64,
+    /// The `max_price` bound of the buy.
+    pub max_price: u64,
+    pub compute_unit_limit: u32,
+    /// The priority fee in micro-lamports per compute unit.
+    pub compute_unit_price: u64,
+    /// Simulate the buy instead of sending it, e.g. against a local validator.
+    pub dry_run: bool,
+}
+
+/// What happened to a snipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnipeOutcome {
+    Simulated {
+        id: u64,
+        first_purchase: bool,
+        units_consumed: Option<u64>,
+        err: Option<TransactionError>,
+        logs: Vec<String>,
+    },
+    Sent {
+        id: u64,
+        first_purchase: bool,
+        signature: Signature,
+        confirmation: Confirmation,
+    },
+}
+
+impl SnipeOutcome {
+    /// Whether the buy went through, or would have for a simulation.
+    pub fn succeeded(&self) -> bool {
+        match self {
+            SnipeOutcome::Simulated { err, .. } => err.is_none(),
+            SnipeOutcome::Sent { confirmation, .. } => *confirmation == Confirmation::Succeeded,
+        }
+    }
+}
+
+/// Returns the verify instructions of the program in the transaction.
+pub fn find_verifications(
+    program: &FriendzyProgram,
+    transaction: &VersionedTransaction,
+) -> Vec<VerifyArgs> {
+    let keys = transaction.message.static_account_keys();
+
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program.program_id))
+        .filter(|ix| ix.data.get(VerifyArgs::PADDING_INDEX) == Some(&VERIFY_DISCRIMINATOR))
+        .filter_map(|ix| VerifyArgs::try_from_slice(&ix.data).ok())
+        .collect()
+}
+
+/// Buys the keys of newly verified profiles.
+///
+/// Every id is sniped at most once, a failed snipe is retried when the id shows up again. A snipe
+/// fails on an RPC error, a simulation error, or a sent buy that fails on-chain or expires, sent
+/// buys are waited on until confirmed. The verify creates the config but not the mint, so the buy
+/// goes through the first purchase path until the mint exists, otherwise someone else was first
+/// and it's a regular buy.
+pub struct Sniper {
+    program: FriendzyProgram,
+    rpc_client: RpcClient,
+    payer: Keypair,
+    config: SnipeConfig,
+    sniped: HashSet<u64>,
+}
+
+impl Sniper {
+    pub fn new(
+        program: FriendzyProgram,
+        rpc_client: RpcClient,
+        payer: Keypair,
+        config: SnipeConfig,
+    ) -> Self {
+        Self {
+            program,
+            rpc_client,
+            payer,
+            config,
+            sniped: HashSet::new(),
+        }
+    }
+
+    pub fn config(&self) -> &SnipeConfig {
+        &self.config
+    }
+
+    /// Builds the buy for the verified id.
+    pub fn build(
+        &self,
+        verify: &VerifyArgs,
+        first_purchase: bool,
+        blockhash: Hash,
+    ) -> TransactionBuilder {
+        TransactionBuilder::new(self.payer.pubkey())
+            .compute_unit_limit(self.config.compute_unit_limit)
+            .compute_unit_price(self.config.compute_unit_price)
+            .blockhash(blockhash)
+            .swap(
+                &self.program,
+                verify.id,
+                self.config.amount,
+                self.config.max_price,
+                crate::Side::Buy,
+                first_purchase,
+            )
+    }
+
+    /// Whether a buy of the id goes through the first purchase path, which creates the mint.
+    pub fn is_first_purchase(&self, id: u64) -> Result<bool, ClientError> {
+        let (mint, _) = self.program.derive_mint_address(id);
+        Ok(self
+            .rpc_client
+            .get_account_with_commitment(&mint, CommitmentConfig::processed())?
+            .value
+            .is_none())
+    }
+
+    /// Snipes the verified id, `None` if it was already sniped.
+    pub fn snipe(&mut self, verify: &VerifyArgs) -> Result<Option<SnipeOutcome>, ClientError> {
+        if self.sniped.contains(&verify.id) {
+            return Ok(None);
+        }
+
+        let outcome = self.buy(verify)?;
+        if outcome.succeeded() {
+            self.sniped.insert(verify.id);
+        }
+        Ok(Some(outcome))
+    }
+
+    fn buy(&self, verify: &VerifyArgs) -> Result<SnipeOutcome, ClientError> {
+        let first_purchase = self.is_first_purchase(verify.id)?;
+        let (blockhash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?;
+        let transaction = self
+            .build(verify, first_purchase, blockhash)
+            .build(&[&self.payer])?;
+
+        if self.config.dry_run {
+            let result = self.rpc_client.simulate_transaction(&transaction)?.value;
+            return Ok(SnipeOutcome::Simulated {
+                id: verify.id,
+                first_purchase,
+                units_consumed: result.units_consumed,
+                err: result.err,
+                logs: result.logs.unwrap_or_default(),
+            });
+        }
+
+        let signature = self.rpc_client.send_transaction_with_config(
+            &transaction,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        )?;
+        let confirmation =
+            confirm_transaction(&self.rpc_client, &signature, last_valid_block_height)?;
+        Ok(SnipeOutcome::Sent {
+            id: verify.id,
+            first_purchase,
+            signature,
+            confirmation,
+        })
+    }
+
+    /// Snipes the verify instructions of a confirmed transaction, with a result per verify that
+    /// wasn't sniped before.
+    pub fn snipe_transaction(
+        &mut self,
+        signature: &Signature,
+    ) -> Result<Vec<Result<SnipeOutcome, ClientError>>, ClientError> {
+        let transaction = self.rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        let Some(transaction) = transaction.transaction.transaction.decode() else {
+            return Ok(vec![]);
+        };
+
+        Ok(find_verifications(&self.program, &transaction)
+            .iter()
+            .filter_map(|verify| self.snipe(verify).transpose())
+            .collect())
+    }
+
+    /// Watches the program's transactions over the websocket and snipes every verification,
+    /// until the subscription closes.
+    pub fn run<F>(&mut self, websocket_url: &str, mut on_outcome: F) -> Result<(), ClientError>
+    where
+        F: FnMut(Result<SnipeOutcome, ClientError>),
+    {
+        let (_subscription, receiver) = PubsubClient::logs_subscribe(
+            websocket_url,
+            RpcTransactionLogsFilter::Mentions(vec![self.program.program_id.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+
+        for response in receiver {
+            if response.value.err.is_some() {
+                continue;
+            }
+            let Ok(signature) = Signature::from_str(&response.value.signature) else {
+                continue;
+            };
+
+            match self.snipe_transaction(&signature) {
+                Ok(outcomes) => outcomes.into_iter().for_each(&mut on_outcome),
+                Err(e) => on_outcome(Err(e)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_swap_instruction_data, create_verify_instruction_data, Config, SwapArgs};
+    use anchor_lang::AnchorSerialize;
+    use async_trait::async_trait;
+    use base64::{engine::general_purpose, Engine};
+    use serde_json::Value;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::{
+        client_error::{ClientErrorKind, Result as RpcResult},
+        rpc_client::RpcClientConfig,
+        rpc_request::RpcRequest,
+        rpc_response::{
+            Response, RpcBlockhash, RpcResponseContext, RpcSimulateTransactionResult,
+            RpcVersionInfo,
+        },
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        message::{v0, VersionedMessage},
+        pubkey::Pubkey,
+    };
+    use solana_transaction_status::{
+        EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+        EncodedTransactionWithStatusMeta, TransactionBinaryEncoding, TransactionConfirmationStatus,
+        TransactionStatus,
+    };
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    /// An RPC serving the accounts and transactions of the maps, every request fails while it's
+    /// down and reading a broken account fails.
+    ///
+    /// Simulations fail with `simulation_err`, sent transactions have the `status` and blockhashes
+    /// are valid until block height 1.
+    #[derive(Default, Clone)]
+    struct StubRpc {
+        accounts: Arc<Mutex<HashMap<Pubkey, Account>>>,
+        broken: Arc<Mutex<HashSet<Pubkey>>>,
+        transactions: Arc<Mutex<HashMap<Signature, VersionedTransaction>>>,
+        simulation_err: Arc<Mutex<Option<TransactionError>>>,
+        status: Arc<Mutex<Option<Result<(), TransactionError>>>>,
+        block_height: Arc<AtomicU64>,
+        down: Arc<AtomicBool>,
+    }
+
+    impl StubRpc {
+        fn insert(&self, address: Pubkey, data: Vec<u8>) {
+            let account = Account {
+                lamports: 1_000_000,
+                data,
+                owner: crate::id(),
+                executable: false,
+                rent_epoch: 0,
+            };
+            self.accounts.lock().unwrap().insert(address, account);
+        }
+    }
+
+    #[async_trait]
+    impl RpcSender for StubRpc {
+        async fn send(&self, request: RpcRequest, params: Value) -> RpcResult<Value> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ClientErrorKind::Custom("rpc is down".to_string()).into());
+            }
+
+            let context = RpcResponseContext {
+                slot: 1,
+                api_version: None,
+            };
+            let value = match request {
+                RpcRequest::GetAccountInfo => {
+                    let address = Pubkey::from_str(params[0].as_str().unwrap()).unwrap();
+                    if self.broken.lock().unwrap().contains(&address) {
+                        return Err(ClientErrorKind::Custom("broken account".to_string()).into());
+                    }
+                    let accounts = self.accounts.lock().unwrap();
+                    let value = accounts.get(&address).map(|account| {
+                        UiAccount::encode(&address, account, UiAccountEncoding::Base64, None, None)
+                    });
+                    serde_json::to_value(Response { context, value })
+                }
+                RpcRequest::GetLatestBlockhash => serde_json::to_value(Response {
+                    context,
+                    value: RpcBlockhash {
+                        blockhash: Hash::new_unique().to_string(),
+                        last_valid_block_height: 1,
+                    },
+                }),
+                RpcRequest::SimulateTransaction => serde_json::to_value(Response {
+                    context,
+                    value: RpcSimulateTransactionResult {
+                        err: self.simulation_err.lock().unwrap().clone(),
+                        logs: None,
+                        accounts: None,
+                        units_consumed: Some(60_000),
+                        return_data: None,
+                    },
+                }),
+                RpcRequest::GetVersion => serde_json::to_value(RpcVersionInfo {
+                    solana_core: "1.16.27".to_string(),
+                    feature_set: None,
+                }),
+                RpcRequest::SendTransaction => {
+                    let data = general_purpose::STANDARD
+                        .decode(params[0].as_str().unwrap())
+                        .unwrap();
+                    let transaction: VersionedTransaction = bincode::deserialize(&data).unwrap();
+                    serde_json::to_value(transaction.signatures[0].to_string())
+                }
+                RpcRequest::GetBlockHeight => {
+                    serde_json::to_value(self.block_height.load(Ordering::SeqCst))
+                }
+                RpcRequest::GetSignatureStatuses => {
+                    let value =
+                        self.status
+                            .lock()
+                            .unwrap()
+                            .clone()
+                            .map(|status| TransactionStatus {
+                                slot: 1,
+                                confirmations: Some(1),
+                                err: status.clone().err(),
+                                status,
+                                confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+                            });
+                    serde_json::to_value(Response {
+                        context,
+                        value: vec![value],
+                    })
+                }
+                RpcRequest::GetTransaction => {
+                    let signature = Signature::from_str(params[0].as_str().unwrap()).unwrap();
+                    let transaction = &self.transactions.lock().unwrap()[&signature];
+                    let data = bincode::serialize(transaction).unwrap();
+                    serde_json::to_value(EncodedConfirmedTransactionWithStatusMeta {
+                        slot: 1,
+                        transaction: EncodedTransactionWithStatusMeta {
+                            transaction: EncodedTransaction::Binary(
+                                general_purpose::STANDARD.encode(data),
+                                TransactionBinaryEncoding::Base64,
+                            ),
+                            meta: None,
+                            version: None,
+                        },
+                        block_time: None,
+                    })
+                }
+                request => panic!("unexpected request {}", request),
+            };
+            Ok(value?)
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "stub".to_string()
+        }
+    }
+
+    fn sniper(rpc_client: RpcClient) -> Sniper {
+        Sniper::new(
+            FriendzyProgram::MAINNET,
+            rpc_client,
+            Keypair::new(),
+            SnipeConfig {
+                amount: 1_000_000_000,
+                max_price: 20_000_000,
+                compute_unit_limit: 300_000,
+                compute_unit_price: 100_000,
+                dry_run: true,
+            },
+        )
+    }
+
+    fn stub_sniper() -> (StubRpc, Sniper) {
+        let rpc = StubRpc::default();
+        let rpc_client = RpcClient::new_sender(rpc.clone(), RpcClientConfig::default());
+        (rpc, sniper(rpc_client))
+    }
+
+    /// Lands a verify of the id the way the program does, creating the config.
+    fn verify(rpc: &StubRpc, id: u64) -> VerifyArgs {
+        let owner = Pubkey::new_unique();
+        let config = Config {
+            id,
+            owner,
+            ..Config::default()
+        };
+        let (address, _) = FriendzyProgram::MAINNET.derive_config_address(id);
+        rpc.insert(address, config.try_to_vec().unwrap());
+        VerifyArgs {
+            version: 0,
+            id,
+            owner,
+        }
+    }
+
+    fn first_purchase(outcome: Option<SnipeOutcome>) -> bool {
+        match outcome {
+            Some(SnipeOutcome::Simulated { first_purchase, .. }) => first_purchase,
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+
+    fn transaction(instructions: &[Instruction]) -> VersionedTransaction {
+        let payer = Pubkey::new_unique();
+        let message =
+            v0::Message::try_compile(&payer, instructions, &[], Hash::new_unique()).unwrap();
+        VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        }
+    }
+
+    #[test]
+    fn test_find_verifications() {
+        let program = FriendzyProgram::MAINNET;
+        let owner = Pubkey::new_unique();
+        let accounts = vec![AccountMeta::new(Pubkey::new_unique(), false)];
+
+        let transaction = transaction(&[
+            Instruction::new_with_bytes(
+                program.program_id,
+                &create_swap_instruction_data(7, 1, 1, crate::Side::Buy),
+                accounts.clone(),
+            ),
+            Instruction::new_with_bytes(
+                program.program_id,
+                &create_verify_instruction_data(&owner, 42),
+                accounts.clone(),
+            ),
+            // the same data for another program is ignored
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &create_verify_instruction_data(&owner, 43),
+                accounts,
+            ),
+        ]);
+
+        assert_eq!(
+            vec![VerifyArgs {
+                version: 0,
+                id: 42,
+                owner
+            }],
+            find_verifications(&program, &transaction)
+        );
+    }
+
+    #[test]
+    fn test_build_first_purchase() {
+        let sniper = sniper(RpcClient::new("http://localhost:8899".to_string()));
+        let verify = VerifyArgs {
+            version: 0,
+            id: 42,
+            owner: Pubkey::new_unique(),
+        };
+
+        let instructions = sniper
+            .build(&verify, true, Hash::new_unique())
+            .instructions();
+        assert_eq!(3, instructions.len());
+
+        let swap = instructions.last().unwrap();
+        assert_eq!(crate::id(), swap.program_id);
+        let args = SwapArgs::try_from_slice(&swap.data).unwrap();
+        assert_eq!(42, args.id);
+        assert_eq!(crate::Side::Buy, args.side);
+        assert_eq!(20_000_000, args.price);
+    }
+
+    #[test]
+    fn test_snipe_after_verify() {
+        let (rpc, mut sniper) = stub_sniper();
+
+        let verify_42 = verify(&rpc, 42);
+        assert!(first_purchase(sniper.snipe(&verify_42).unwrap()));
+        assert_eq!(None, sniper.snipe(&verify_42).unwrap());
+
+        // someone else bought first and created the mint
+        let verify_43 = verify(&rpc, 43);
+        let (mint, _) = FriendzyProgram::MAINNET.derive_mint_address(43);
+        rpc.insert(mint, vec![0; 82]);
+        assert!(!first_purchase(sniper.snipe(&verify_43).unwrap()));
+    }
+
+    #[test]
+    fn test_snipe_retries_after_simulation_error() {
+        let (rpc, mut sniper) = stub_sniper();
+        let verify = verify(&rpc, 42);
+
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(0));
+        *rpc.simulation_err.lock().unwrap() = Some(err.clone());
+        let outcome = sniper.snipe(&verify).unwrap().unwrap();
+        assert!(matches!(&outcome, SnipeOutcome::Simulated { err: Some(e), .. } if *e == err));
+        assert!(!outcome.succeeded());
+
+        *rpc.simulation_err.lock().unwrap() = None;
+        assert!(first_purchase(sniper.snipe(&verify).unwrap()));
+        assert_eq!(None, sniper.snipe(&verify).unwrap());
+    }
+
+    #[test]
+    fn test_snipe_waits_for_confirmation() {
+        let (rpc, mut sniper) = stub_sniper();
+        sniper.config.dry_run = false;
+        let verify = verify(&rpc, 42);
+        let confirmation = |outcome: Option<SnipeOutcome>| match outcome {
+            Some(SnipeOutcome::Sent { confirmation, .. }) => confirmation,
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        };
+
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(0));
+        *rpc.status.lock().unwrap() = Some(Err(err.clone()));
+        assert_eq!(
+            Confirmation::Failed(err),
+            confirmation(sniper.snipe(&verify).unwrap())
+        );
+
+        // past the last valid block height the buy never landed
+        *rpc.status.lock().unwrap() = None;
+        rpc.block_height.store(2, Ordering::SeqCst);
+        assert_eq!(
+            Confirmation::Expired,
+            confirmation(sniper.snipe(&verify).unwrap())
+        );
+
+        *rpc.status.lock().unwrap() = Some(Ok(()));
+        assert_eq!(
+            Confirmation::Succeeded,
+            confirmation(sniper.snipe(&verify).unwrap())
+        );
+        assert_eq!(None, sniper.snipe(&verify).unwrap());
+    }
+
+    #[test]
+    fn test_snipe_transaction_per_verify() {
+        let (rpc, mut sniper) = stub_sniper();
+        let program = FriendzyProgram::MAINNET;
+        let verifies = [verify(&rpc, 42), verify(&rpc, 43)];
+        let accounts = vec![AccountMeta::new(Pubkey::new_unique(), false)];
+        let transaction = transaction(
+            &verifies
+                .iter()
+                .map(|verify| {
+                    Instruction::new_with_bytes(
+                        program.program_id,
+                        &create_verify_instruction_data(&verify.owner, verify.id),
+                        accounts.clone(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        let signature = Signature::new_unique();
+        rpc.transactions
+            .lock()
+            .unwrap()
+            .insert(signature, transaction);
+
+        // the first verify failing doesn't stop the second
+        let (mint, _) = program.derive_mint_address(42);
+        rpc.broken.lock().unwrap().insert(mint);
+        let outcomes = sniper.snipe_transaction(&signature).unwrap();
+        assert_eq!(2, outcomes.len());
+        assert!(outcomes[0].is_err());
+        assert!(matches!(
+            outcomes[1],
+            Ok(SnipeOutcome::Simulated { id: 43, .. })
+        ));
+
+        rpc.broken.lock().unwrap().clear();
+        let outcomes = sniper.snipe_transaction(&signature).unwrap();
+        assert_eq!(1, outcomes.len());
+        assert!(matches!(
+            outcomes[0],
+            Ok(SnipeOutcome::Simulated { id: 42, .. })
+        ));
+    }
+
+    #[test]
+    fn test_snipe_retries_after_error() {
+        let (rpc, mut sniper) = stub_sniper();
+        let verify = verify(&rpc, 42);
+
+        rpc.down.store(true, Ordering::SeqCst);
+        assert!(sniper.snipe(&verify).is_err());
+
+        rpc.down.store(false, Ordering::SeqCst);
+        assert!(first_purchase(sniper.snipe(&verify).unwrap()));
+    }
+}
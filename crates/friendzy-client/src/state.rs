@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::{ui, DECIMAL_DENOMINATOR};
+
 // Config: [id, supply, owner, royalties, unclaimed, debt]
 // Profile: [id, owner, buy_amount, sell_amount, buy_volume, sell_volume, reserved]
 // All u64 except owner which are pubkeys
@@ -15,6 +17,9 @@ pub struct Config {
 }
 
 impl Config {
+    pub const LEN: usize = 72;
+    pub const OWNER_OFFSET: usize = 16;
+
     pub fn claimed_royalties(&self) -> u64 {
         self.royalties - self.unclaimed
     }
@@ -28,10 +33,64 @@ pub struct Profile {
     pub sell_amount: u64,
     pub buy_volume: u64,
     pub sell_volume: u64,
+    /// Zero in every profile seen so far, its meaning hasn't been decoded.
     pub reserved: u64,
 }
 
-impl Profile {}
+/// The lamports paid per whole key, `None` without keys or on overflow.
+fn average_price(volume: u64, amount: u64) -> Option<u64> {
+    if amount == 0 {
+        return None;
+    }
+    u64::try_from(volume as u128 * DECIMAL_DENOMINATOR as u128 / amount as u128).ok()
+}
+
+impl Profile {
+    pub const LEN: usize = 80;
+    pub const OWNER_OFFSET: usize = 8;
+
+    /// The keys bought minus the keys sold, `None` when more were sold than bought, e.g. when
+    /// keys were transferred in.
+    pub fn net_amount(&self) -> Option<u64> {
+        self.buy_amount.checked_sub(self.sell_amount)
+    }
+
+    pub fn net_amount_ui(&self) -> Option<f64> {
+        self.net_amount().map(ui)
+    }
+
+    /// The lamports received minus the lamports paid, the realized profit of a flat profile.
+    pub fn net_volume(&self) -> i128 {
+        self.sell_volume as i128 - self.buy_volume as i128
+    }
+
+    pub fn net_volume_ui(&self) -> f64 {
+        self.net_volume() as f64 / DECIMAL_DENOMINATOR as f64
+    }
+
+    /// The average lamports paid per whole key, `None` when nothing was bought.
+    pub fn average_buy_price(&self) -> Option<u64> {
+        average_price(self.buy_volume, self.buy_amount)
+    }
+
+    pub fn average_buy_price_ui(&self) -> Option<f64> {
+        self.average_buy_price().map(ui)
+    }
+
+    /// The average lamports received per whole key, `None` when nothing was sold.
+    pub fn average_sell_price(&self) -> Option<u64> {
+        average_price(self.sell_volume, self.sell_amount)
+    }
+
+    pub fn average_sell_price_ui(&self) -> Option<f64> {
+        self.average_sell_price().map(ui)
+    }
+
+    /// Whether every key bought was sold.
+    pub fn is_flat(&self) -> bool {
+        self.buy_amount == self.sell_amount
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -47,6 +106,7 @@ mod tests {
     #[test]
     fn test_load_config() -> Result<()> {
         let data = decode_base64("AKDXUiVUIRAALr5DLwAAAApz5x/t0hNl7QruhPzk4rIGR/001ey9oRXwI9JjP4d4QBxARgAAAAApndAJAAAAAAAAAAAAAAAA");
+        assert_eq!(Config::LEN, data.len());
         let config = Config::try_from_slice(&data).unwrap();
 
         assert_eq!(
@@ -68,6 +128,7 @@ mod tests {
     #[test]
     fn test_load_profile() -> Result<()> {
         let data = decode_base64("AKDXUiVUIRAKc+cf7dITZe0K7oT85OKyBkf9NNXsvaEV8CPSYz+HeAC4mj4KAAAAAAAAAAAAAADLyIBbAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        assert_eq!(Profile::LEN, data.len());
         let profile = Profile::try_from_slice(&data).unwrap();
 
         assert_eq!(
@@ -82,4 +143,39 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_profile_stats() -> Result<()> {
+        let mut profile = Profile {
+            buy_amount: 44_000_000_000,
+            buy_volume: 1_535_166_667,
+            ..Profile::default()
+        };
+        assert_eq!(Some(44_000_000_000), profile.net_amount());
+        assert_eq!(Some(44.0), profile.net_amount_ui());
+        assert_eq!(Some(34_890_151), profile.average_buy_price());
+        assert_eq!(None, profile.average_sell_price());
+        assert_eq!(-1_535_166_667, profile.net_volume());
+        assert!(!profile.is_flat());
+
+        profile.sell_amount = 44_000_000_000;
+        profile.sell_volume = 1_700_000_000;
+        assert!(profile.is_flat());
+        assert_eq!(Some(0), profile.net_amount());
+        assert_eq!(Some(38_636_363), profile.average_sell_price());
+        assert_eq!(0.164833333, profile.net_volume_ui());
+
+        // keys transferred in and sold
+        profile.sell_amount = 45_000_000_000;
+        assert_eq!(None, profile.net_amount());
+
+        let whale = Profile {
+            buy_amount: 1,
+            buy_volume: u64::MAX,
+            ..Profile::default()
+        };
+        assert_eq!(None, whale.average_buy_price());
+
+        Ok(())
+    }
 }
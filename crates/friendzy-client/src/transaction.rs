@@ -0,0 +1,372 @@
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::signers::Signers,
+    system_instruction,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use std::{thread, time::Duration};
+
+use crate::{error::ClientError, program::FriendzyProgram, Side};
+
+/// Where the transaction lifetime comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockhashSource {
+    /// A recent blockhash, the transaction expires after ~150 slots.
+    Blockhash(Hash),
+    /// The stored hash of a durable nonce account, the nonce is advanced as the first instruction.
+    DurableNonce {
+        nonce_account: Pubkey,
+        authority: Pubkey,
+        nonce: Hash,
+    },
+}
+
+/// Assembles Friendzy instructions into a v0 [`VersionedTransaction`].
+///
+/// The compute budget instructions are only added when set, the unit price is in micro-lamports
+/// per compute unit.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    blockhash: Option<BlockhashSource>,
+}
+
+impl TransactionBuilder {
+    pub fn new(payer: Pubkey) -> Self {
+        Self {
+            payer,
+            instructions: vec![],
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            lookup_tables: vec![],
+            blockhash: None,
+        }
+    }
+
+    pub fn payer(&self) -> &Pubkey {
+        &self.payer
+    }
+
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    pub fn blockhash(mut self, blockhash: Hash) -> Self {
+        self.blockhash = Some(BlockhashSource::Blockhash(blockhash));
+        self
+    }
+
+    pub fn durable_nonce(mut self, nonce_account: Pubkey, authority: Pubkey, nonce: Hash) -> Self {
+        self.blockhash = Some(BlockhashSource::DurableNonce {
+            nonce_account,
+            authority,
+            nonce,
+        });
+        self
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Creates the payer's token account for the mint if it does not exist yet.
+    pub fn create_token_account(self, mint: &Pubkey) -> Self {
+        let payer = self.payer;
+        self.instruction(create_associated_token_account_idempotent(
+            &payer,
+            &payer,
+            mint,
+            &anchor_spl::token::ID,
+        ))
+    }
+
+    /// Adds a swap for the payer, deriving every account and creating the token account first.
+    pub fn swap(
+        self,
+        program: &FriendzyProgram,
+        id: u64,
+        amount: u64,
+        price: u64,
+        side: Side,
+        first_purchase: bool,
+    ) -> Self {
+        let accounts = program.swap_accounts(id, &self.payer);
+
+        // on a first purchase the mint does not exist yet and the program creates the token account
+        let builder = if first_purchase {
+            self
+        } else {
+            self.create_token_account(&accounts.token_mint)
+        };
+
+        builder.instruction(program.swap(&accounts, first_purchase, id, amount, price, side))
+    }
+
+    /// Keeps only the added instructions the predicate holds for, the compute budget and nonce
+    /// instructions are not affected.
+    pub fn retain_instructions(mut self, f: impl FnMut(&Instruction) -> bool) -> Self {
+        self.instructions.retain(f);
+        self
+    }
+
+    /// Returns every instruction in the order they will be executed.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = vec![];
+
+        if let Some(BlockhashSource::DurableNonce {
+            nonce_account,
+            authority,
+            ..
+        }) = self.blockhash
+        {
+            instructions.push(system_instruction::advance_nonce_account(
+                &nonce_account,
+                &authority,
+            ));
+        }
+        if let Some(units) = self.compute_unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+
+        instructions.extend(self.instructions.iter().cloned());
+        instructions
+    }
+
+    pub fn build_message(&self) -> Result<VersionedMessage, ClientError> {
+        let blockhash = match self.blockhash {
+            Some(BlockhashSource::Blockhash(blockhash)) => blockhash,
+            Some(BlockhashSource::DurableNonce { nonce, .. }) => nonce,
+            None => return Err(ClientError::MissingBlockhash),
+        };
+
+        let message = v0::Message::try_compile(
+            &self.payer,
+            &self.instructions(),
+            &self.lookup_tables,
+            blockhash,
+        )?;
+
+        Ok(VersionedMessage::V0(message))
+    }
+
+    /// Builds the transaction with placeholder signatures, to simulate without signing.
+    ///
+    /// Without a blockhash the default hash is used, the RPC is expected to replace it.
+    pub fn build_unsigned(&self) -> Result<VersionedTransaction, ClientError> {
+        let message = match self.blockhash {
+            Some(_) => self.build_message()?,
+            None => self.clone().blockhash(Hash::default()).build_message()?,
+        };
+        let signatures =
+            vec![Signature::default(); message.header().num_required_signatures as usize];
+
+        Ok(VersionedTransaction {
+            signatures,
+            message,
+        })
+    }
+
+    pub fn build<T: Signers + ?Sized>(
+        &self,
+        signers: &T,
+    ) -> Result<VersionedTransaction, ClientError> {
+        Ok(VersionedTransaction::try_new(
+            self.build_message()?,
+            signers,
+        )?)
+    }
+}
+
+/// The status of a sent transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confirmation {
+    /// The transaction landed and succeeded.
+    Succeeded,
+    /// The transaction landed and failed, e.g. on the price bound.
+    Failed(TransactionError),
+    /// The blockhash expired before the transaction landed, it can't land anymore.
+    Expired,
+    /// The transaction hasn't landed yet but still can.
+    Pending,
+}
+
+/// Checks the status of a sent transaction at the confirmed commitment, `last_valid_block_height`
+/// is the one returned with its blockhash.
+pub fn check_confirmation(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    last_valid_block_height: u64,
+) -> Result<Confirmation, ClientError> {
+    // the block height is read first, a transaction landing in between can't be taken for expired
+    let block_height = rpc_client.get_block_height_with_commitment(CommitmentConfig::confirmed())?;
+    let status = rpc_client
+        .get_signature_status_with_commitment(signature, CommitmentConfig::confirmed())?;
+
+    Ok(match status {
+        Some(Ok(())) => Confirmation::Succeeded,
+        Some(Err(err)) => Confirmation::Failed(err),
+        None if block_height > last_valid_block_height => Confirmation::Expired,
+        None => Confirmation::Pending,
+    })
+}
+
+/// Waits until a sent transaction succeeded, failed or expired, checking twice a second.
+pub fn confirm_transaction(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    last_valid_block_height: u64,
+) -> Result<Confirmation, ClientError> {
+    loop {
+        match check_confirmation(rpc_client, signature, last_valid_block_height)? {
+            Confirmation::Pending => thread::sleep(Duration::from_millis(500)),
+            confirmation => return Ok(confirmation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{compute_budget, signature::Keypair, signer::Signer, system_program};
+
+    #[test]
+    fn test_build_swap_transaction() {
+        let keypair = Keypair::new();
+        let program = FriendzyProgram::MAINNET;
+
+        let tx = TransactionBuilder::new(keypair.pubkey())
+            .compute_unit_limit(100_000)
+            .compute_unit_price(50_000)
+            .blockhash(Hash::new_unique())
+            .swap(&program, 42, 1_000_000_000, 100_000_000, Side::Buy, false)
+            .build(&[&keypair])
+            .unwrap();
+
+        assert!(matches!(tx.message, VersionedMessage::V0(_)));
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+
+        let keys = tx.message.static_account_keys();
+        let programs: Vec<Pubkey> = tx
+            .message
+            .instructions()
+            .iter()
+            .map(|ix| keys[ix.program_id_index as usize])
+            .collect();
+        assert_eq!(
+            vec![
+                compute_budget::id(),
+                compute_budget::id(),
+                spl_associated_token_account::id(),
+                program.program_id,
+            ],
+            programs
+        );
+    }
+
+    #[test]
+    fn test_first_purchase_skips_token_account() {
+        let payer = Pubkey::new_unique();
+        let instructions = TransactionBuilder::new(payer)
+            .swap(
+                &FriendzyProgram::MAINNET,
+                42,
+                1_000_000_000,
+                100_000_000,
+                Side::Buy,
+                true,
+            )
+            .instructions();
+
+        assert_eq!(1, instructions.len());
+        assert_eq!(crate::id(), instructions[0].program_id);
+    }
+
+    #[test]
+    fn test_durable_nonce_is_first() {
+        let payer = Pubkey::new_unique();
+        let nonce_account = Pubkey::new_unique();
+        let nonce = Hash::new_unique();
+
+        let builder = TransactionBuilder::new(payer)
+            .compute_unit_price(1)
+            .durable_nonce(nonce_account, payer, nonce)
+            .swap(
+                &FriendzyProgram::MAINNET,
+                42,
+                1_000_000_000,
+                0,
+                Side::Sell,
+                false,
+            );
+
+        let instructions = builder.instructions();
+        assert_eq!(system_program::id(), instructions[0].program_id);
+        assert_eq!(nonce_account, instructions[0].accounts[0].pubkey);
+
+        let message = builder.build_message().unwrap();
+        assert_eq!(&nonce, message.recent_blockhash());
+    }
+
+    #[test]
+    fn test_lookup_table_shrinks_static_keys() {
+        let payer = Pubkey::new_unique();
+        let program = FriendzyProgram::MAINNET;
+        let builder = TransactionBuilder::new(payer)
+            .blockhash(Hash::new_unique())
+            .swap(&program, 42, 1_000_000_000, 100_000_000, Side::Buy, false);
+
+        let without = builder.build_message().unwrap();
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![
+                program.derive_bank_address().0,
+                program.vault,
+                anchor_spl::token::ID,
+            ],
+        };
+        let with = builder.lookup_table(table).build_message().unwrap();
+
+        assert_eq!(
+            without.static_account_keys().len() - 3,
+            with.static_account_keys().len()
+        );
+    }
+
+    #[test]
+    fn test_missing_blockhash() {
+        let result = TransactionBuilder::new(Pubkey::new_unique()).build_message();
+        assert!(matches!(result, Err(ClientError::MissingBlockhash)));
+    }
+}
@@ -0,0 +1,347 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+};
+use std::fmt;
+
+use crate::{program::FriendzyProgram, SwapArgs, WithdrawArgs};
+
+/// The names of the swap accounts by position, with the first purchase names of 8 and 9.
+const SWAP_ACCOUNTS: [&str; 13] = [
+    "user",
+    "bank",
+    "config",
+    "token mint",
+    "profile",
+    "token program",
+    "rent",
+    "system program",
+    "metadata",
+    "metaplex program",
+    "token account",
+    "vault",
+    "associated token program",
+];
+
+/// Returns the names of the swap accounts, 8 and 9 are placeholders unless it's a first purchase.
+pub(crate) fn swap_account_names(first_purchase: bool) -> &'static [&'static str] {
+    const PLACEHOLDERS: [&str; 13] = {
+        let mut names = SWAP_ACCOUNTS;
+        names[8] = "placeholder";
+        names[9] = "placeholder";
+        names
+    };
+
+    match first_purchase {
+        true => &SWAP_ACCOUNTS,
+        false => &PLACEHOLDERS,
+    }
+}
+
+/// The names of the withdraw accounts by position.
+pub(crate) const WITHDRAW_ACCOUNTS: [&str; 11] = [
+    "user",
+    "bank",
+    "config",
+    "token mint",
+    "profile",
+    "token program",
+    "rent",
+    "system program",
+    "placeholder",
+    "placeholder",
+    "placeholder",
+];
+
+/// A difference between an instruction and the one expected from its data and signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    ProgramId {
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    /// The data can't be decoded as the instruction.
+    InvalidData,
+    AccountCount {
+        expected: usize,
+        actual: usize,
+    },
+    Account {
+        index: usize,
+        name: &'static str,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    Writable {
+        index: usize,
+        name: &'static str,
+        expected: bool,
+    },
+    Signer {
+        index: usize,
+        name: &'static str,
+        expected: bool,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::ProgramId { expected, actual } => {
+                write!(f, "program id is {}, expected {}", actual, expected)
+            }
+            Mismatch::InvalidData => write!(f, "instruction data can't be decoded"),
+            Mismatch::AccountCount { expected, actual } => {
+                write!(f, "{} accounts, expected {}", actual, expected)
+            }
+            Mismatch::Account {
+                index,
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "account {} ({}) is {}, expected {}",
+                index, name, actual, expected
+            ),
+            Mismatch::Writable {
+                index,
+                name,
+                expected,
+            } => match expected {
+                true => write!(f, "account {} ({}) must be writable", index, name),
+                false => write!(f, "account {} ({}) must be read-only", index, name),
+            },
+            Mismatch::Signer {
+                index,
+                name,
+                expected,
+            } => match expected {
+                true => write!(f, "account {} ({}) must sign", index, name),
+                false => write!(f, "account {} ({}) must not sign", index, name),
+            },
+        }
+    }
+}
+
+/// Compares the instruction to the expected one account by account.
+fn compare(actual: &Instruction, expected: &Instruction, names: &[&'static str]) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+
+    if actual.program_id != expected.program_id {
+        mismatches.push(Mismatch::ProgramId {
+            expected: expected.program_id,
+            actual: actual.program_id,
+        });
+    }
+    if actual.accounts.len() != expected.accounts.len() {
+        mismatches.push(Mismatch::AccountCount {
+            expected: expected.accounts.len(),
+            actual: actual.accounts.len(),
+        });
+    }
+
+    for (index, (actual, expected)) in actual.accounts.iter().zip(&expected.accounts).enumerate() {
+        let name = names[index];
+        if actual.pubkey != expected.pubkey {
+            mismatches.push(Mismatch::Account {
+                index,
+                name,
+                expected: expected.pubkey,
+                actual: actual.pubkey,
+            });
+        }
+        if actual.is_writable != expected.is_writable {
+            mismatches.push(Mismatch::Writable {
+                index,
+                name,
+                expected: expected.is_writable,
+            });
+        }
+        if actual.is_signer != expected.is_signer {
+            mismatches.push(Mismatch::Signer {
+                index,
+                name,
+                expected: expected.is_signer,
+            });
+        }
+    }
+
+    mismatches
+}
+
+impl FriendzyProgram {
+    /// Checks a swap against the accounts derived from its data and the user at position 0.
+    ///
+    /// A first purchase is recognized by a metadata account in place of the placeholder at 8.
+    pub fn validate_swap(&self, instruction: &Instruction) -> Vec<Mismatch> {
+        let Ok(args) = SwapArgs::try_from_slice(&instruction.data) else {
+            return vec![Mismatch::InvalidData];
+        };
+        let Some(user) = instruction.accounts.first().map(|meta| meta.pubkey) else {
+            return vec![Mismatch::AccountCount {
+                expected: SWAP_ACCOUNTS.len(),
+                actual: 0,
+            }];
+        };
+        let first_purchase = instruction
+            .accounts
+            .get(8)
+            .is_some_and(|meta| meta.pubkey != system_program::ID);
+
+        let expected = self.swap(
+            &self.swap_accounts(args.id, &user),
+            first_purchase,
+            args.id,
+            args.amount,
+            args.price,
+            args.side,
+        );
+
+        compare(instruction, &expected, swap_account_names(first_purchase))
+    }
+
+    /// Checks a withdraw against the accounts derived from its data and the user at position 0.
+    pub fn validate_withdraw(&self, instruction: &Instruction) -> Vec<Mismatch> {
+        let Ok(args) = WithdrawArgs::try_from_slice(&instruction.data) else {
+            return vec![Mismatch::InvalidData];
+        };
+        let Some(user) = instruction.accounts.first().map(|meta| meta.pubkey) else {
+            return vec![Mismatch::AccountCount {
+                expected: WITHDRAW_ACCOUNTS.len(),
+                actual: 0,
+            }];
+        };
+
+        let (bank, _) = self.derive_bank_address();
+        let (mint, _) = self.derive_mint_address(args.id);
+        let (config, _) = self.derive_config_address(args.id);
+        let (profile, _) = self.derive_profile_address(args.id, &user);
+        let expected = self.withdraw(&user, &bank, &config, &mint, &profile, args.id);
+
+        compare(instruction, &expected, &WITHDRAW_ACCOUNTS)
+    }
+}
+
+pub fn validate_swap(instruction: &Instruction) -> Vec<Mismatch> {
+    FriendzyProgram::MAINNET.validate_swap(instruction)
+}
+
+pub fn validate_withdraw(instruction: &Instruction) -> Vec<Mismatch> {
+    FriendzyProgram::MAINNET.validate_withdraw(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pda::*, Side};
+    use spl_associated_token_account::get_associated_token_address;
+    use std::str::FromStr;
+
+    fn swap_instruction(user: &Pubkey, profile_user: &Pubkey, first_purchase: bool) -> Instruction {
+        let id = 1_436_880_221_354_045_450;
+        let (bank, _) = derive_bank_address();
+        let (mint, _) = derive_mint_address(id);
+        let (config, _) = derive_config_address(id);
+        let (profile, _) = derive_profile_address(id, profile_user);
+        let (metadata, _) = derive_metadata_address(&mint);
+        let token_account = get_associated_token_address(user, &mint);
+
+        crate::swap(
+            user,
+            &bank,
+            &config,
+            &mint,
+            &profile,
+            &metadata,
+            &token_account,
+            first_purchase,
+            id,
+            1_000_000_000,
+            100_000_000,
+            Side::Buy,
+        )
+    }
+
+    #[test]
+    fn test_validate_swap() -> Result<()> {
+        let user = Pubkey::from_str("Gf3sbc5Jb62jH7WcTr3WSNGDQLk1w6wcKMZXKK1SC1E6").unwrap();
+        assert!(validate_swap(&swap_instruction(&user, &user, false)).is_empty());
+        assert!(validate_swap(&swap_instruction(&user, &user, true)).is_empty());
+
+        // the profile derived from the wrong user
+        let other = Pubkey::new_unique();
+        let mismatches = validate_swap(&swap_instruction(&user, &other, false));
+        assert_eq!(1, mismatches.len());
+        assert!(matches!(
+            mismatches[0],
+            Mismatch::Account {
+                index: 4,
+                name: "profile",
+                ..
+            }
+        ));
+
+        let mut instruction = swap_instruction(&user, &user, false);
+        instruction.accounts[0].is_signer = false;
+        instruction.accounts[11].is_writable = false;
+        instruction.accounts.pop();
+        assert_eq!(
+            vec![
+                Mismatch::AccountCount {
+                    expected: 13,
+                    actual: 12
+                },
+                Mismatch::Signer {
+                    index: 0,
+                    name: "user",
+                    expected: true
+                },
+                Mismatch::Writable {
+                    index: 11,
+                    name: "vault",
+                    expected: true
+                },
+            ],
+            validate_swap(&instruction)
+        );
+        assert_eq!(
+            "account 11 (vault) must be writable",
+            validate_swap(&instruction)[2].to_string()
+        );
+
+        instruction.data.pop();
+        assert_eq!(vec![Mismatch::InvalidData], validate_swap(&instruction));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_withdraw() -> Result<()> {
+        let id = 42;
+        let user = Pubkey::new_unique();
+        let (bank, _) = derive_bank_address();
+        let (mint, _) = derive_mint_address(id);
+        let (config, _) = derive_config_address(id);
+        let (profile, _) = derive_profile_address(id, &user);
+
+        let mut instruction = crate::withdraw(&user, &bank, &config, &mint, &profile, id);
+        assert!(validate_withdraw(&instruction).is_empty());
+
+        instruction.program_id = Pubkey::new_unique();
+        instruction.accounts[2].pubkey = derive_config_address(id + 1).0;
+        let mismatches = validate_withdraw(&instruction);
+        assert_eq!(2, mismatches.len());
+        assert!(matches!(mismatches[0], Mismatch::ProgramId { .. }));
+        assert!(matches!(
+            mismatches[1],
+            Mismatch::Account {
+                index: 2,
+                name: "config",
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+}
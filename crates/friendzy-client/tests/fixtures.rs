@@ -0,0 +1,470 @@
+// The tests return anchor's `Result` like the crate's own, its `Error` is larger than clippy allows.
+#![allow(clippy::result_large_err)]
+
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use base64::{engine::general_purpose, Engine};
+use friendzy_client::*;
+use serde_json::{json, Value};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+};
+use std::{collections::HashMap, fs, path::PathBuf, str::FromStr};
+
+/// The scenarios the recorded corpus is expected to cover.
+const SCENARIOS: [&str; 9] = [
+    "first_purchase",
+    "buy",
+    "repeat_buy",
+    "sell",
+    "sell_out",
+    "swap",
+    "withdraw",
+    "verify",
+    "snapshot",
+];
+
+/// The fee schedule assumed by the tests, the rates have not been confirmed on-chain.
+const FEES: FeeSchedule = FeeSchedule {
+    royalty_bps: 500,
+    protocol_bps: 500,
+};
+
+fn load_fixtures(dir: &str) -> Vec<(String, Value)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let fixture = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            (name, fixture)
+        })
+        .collect()
+}
+
+/// The fixtures recorded from mainnet transactions with `record_fixture`.
+fn recorded_fixtures() -> Vec<(String, Value)> {
+    load_fixtures("tests/fixtures")
+}
+
+/// The fixtures that weren't recorded from a transaction, they are replayed but don't count
+/// towards the scenario coverage.
+fn synthetic_fixtures() -> Vec<(String, Value)> {
+    load_fixtures("tests/fixtures/synthetic")
+}
+
+fn decode_base64(data: &str) -> Vec<u8> {
+    general_purpose::STANDARD.decode(data).unwrap()
+}
+
+fn pubkey(value: &Value) -> Pubkey {
+    Pubkey::from_str(value.as_str().unwrap()).unwrap()
+}
+
+fn u64_field(value: &Value) -> u64 {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+fn side(value: &Value) -> Side {
+    match value.as_str().unwrap() {
+        "Buy" => Side::Buy,
+        "Sell" => Side::Sell,
+        side => panic!("unknown side {side}"),
+    }
+}
+
+/// Decodes the data, checks it against the expectation and re-encodes it byte for byte.
+fn check_instruction_data(name: &str, data: &[u8], expected: &Value) {
+    match expected["kind"].as_str().unwrap() {
+        "swap" => {
+            let args = SwapArgs::try_from_slice(data).unwrap();
+            assert_eq!(u64_field(&expected["id"]), args.id, "{name}");
+            assert_eq!(side(&expected["side"]), args.side, "{name}");
+            assert_eq!(u64_field(&expected["amount"]), args.amount, "{name}");
+            assert_eq!(u64_field(&expected["price"]), args.price, "{name}");
+            let extra = (!expected["extra"].is_null()).then(|| u64_field(&expected["extra"]));
+            assert_eq!(extra, args.extra, "{name}");
+            assert_eq!(data, args.to_instruction_data(), "{name}");
+
+            // a buy bound can never be below the cost of the keys at zero supply
+            if args.side == Side::Buy {
+                assert!(
+                    args.price >= calculate_buy_cost(0, args.amount).unwrap(),
+                    "{name}"
+                );
+            }
+        }
+        "withdraw" => {
+            let args = WithdrawArgs::try_from_slice(data).unwrap();
+            assert_eq!(u64_field(&expected["id"]), args.id, "{name}");
+            assert_eq!(data, create_withdraw_instruction_data(args.id), "{name}");
+        }
+        "verify" => {
+            let args = VerifyArgs::try_from_slice(data).unwrap();
+            assert_eq!(u64_field(&expected["id"]), args.id, "{name}");
+            assert_eq!(pubkey(&expected["owner"]), args.owner, "{name}");
+            assert_eq!(
+                data,
+                create_verify_instruction_data(&args.owner, args.id),
+                "{name}"
+            );
+        }
+        kind => panic!("{name}: unknown instruction kind {kind}"),
+    }
+}
+
+/// Re-derives every named account from the id and the user.
+fn check_instruction_accounts(
+    name: &str,
+    accounts: &Value,
+    first_purchase: &Value,
+    expected: &Value,
+) {
+    let id = u64_field(&expected["id"]);
+    let addresses = FriendzyAddresses::for_id(id);
+    let accounts = accounts.as_object().unwrap();
+
+    // only a first purchase passes the metadata account
+    if let Some(first_purchase) = first_purchase.as_bool() {
+        assert_eq!(
+            first_purchase,
+            accounts.contains_key("metadata"),
+            "{name}: first_purchase"
+        );
+    }
+
+    for (account, address) in accounts {
+        let address = pubkey(address);
+        let derived = match account.as_str() {
+            "user" => continue,
+            "bank" => derive_bank_address().0,
+            "config" => addresses.config,
+            "mint" => addresses.mint,
+            "metadata" => addresses.metadata,
+            "profile" => derive_profile_address(id, &pubkey(&accounts["user"])).0,
+            account => panic!("{name}: unknown account {account}"),
+        };
+        assert_eq!(derived, address, "{name}: {account}");
+    }
+}
+
+/// Rebuilds the instruction from its ordered account list, checks it against the accounts derived
+/// from its data and that the list takes the first purchase path the fixture says it does.
+fn check_account_keys(name: &str, data: &[u8], keys: &Value, first_purchase: &Value) {
+    let accounts = keys
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|key| AccountMeta {
+            pubkey: pubkey(&key["pubkey"]),
+            is_signer: key["signer"].as_bool().unwrap(),
+            is_writable: key["writable"].as_bool().unwrap(),
+        })
+        .collect();
+    let instruction = Instruction {
+        program_id: friendzy_client::id(),
+        accounts,
+        data: data.to_vec(),
+    };
+
+    let explanation = Explainer::new().explain(&instruction).unwrap();
+    assert!(
+        explanation.mismatches.is_empty(),
+        "{name}: {:?}",
+        explanation.mismatches
+    );
+
+    let decoded = match explanation.action {
+        Action::Buy { first_purchase, .. } => first_purchase,
+        _ => false,
+    };
+    assert_eq!(
+        first_purchase.as_bool(),
+        Some(decoded),
+        "{name}: first_purchase"
+    );
+}
+
+/// Checks a recorded fixture is complete and that its instructions are the Friendzy instructions of
+/// its transaction, with the accounts loaded from lookup tables resolved.
+fn check_recorded(name: &str, fixture: &Value) {
+    assert!(fixture["source"].is_string(), "{name}: source");
+    let encoded: EncodedConfirmedTransactionWithStatusMeta =
+        serde_json::from_value(fixture["transaction"].clone()).unwrap();
+    let transaction = encoded.transaction.transaction.decode().unwrap();
+    let message = &transaction.message;
+
+    let mut keys = message.static_account_keys().to_vec();
+    if let Some(OptionSerializer::Some(loaded)) = encoded
+        .transaction
+        .meta
+        .as_ref()
+        .map(|meta| &meta.loaded_addresses)
+    {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .map(|key| Pubkey::from_str(key).unwrap()),
+        );
+    }
+
+    let recorded: Vec<Value> = message
+        .instructions()
+        .iter()
+        .filter(|ix| keys[ix.program_id_index as usize] == friendzy_client::id())
+        .map(|ix| {
+            let account_keys: Vec<Value> = ix
+                .accounts
+                .iter()
+                .map(|index| {
+                    let index = *index as usize;
+                    json!({
+                        "pubkey": keys[index].to_string(),
+                        "signer": message.is_signer(index),
+                        "writable": message.is_maybe_writable(index),
+                    })
+                })
+                .collect();
+            json!({
+                "data": general_purpose::STANDARD.encode(&ix.data),
+                "account_keys": account_keys,
+            })
+        })
+        .collect();
+
+    let instructions = fixture["instructions"].as_array().unwrap();
+    assert_eq!(recorded.len(), instructions.len(), "{name}: instructions");
+    for (recorded, instruction) in recorded.iter().zip(instructions) {
+        assert_eq!(recorded["data"], instruction["data"], "{name}: data");
+        assert_eq!(
+            recorded["account_keys"], instruction["account_keys"],
+            "{name}: account_keys"
+        );
+    }
+
+    // the accounts are fetched after the transaction, never before it
+    assert!(
+        fixture["accounts_slot"].as_u64().unwrap() >= encoded.slot,
+        "{name}: accounts_slot"
+    );
+}
+
+/// Decodes the account snapshot, checks it lives at the derived address and that the curve agrees.
+fn check_account(name: &str, snapshot: &Value) {
+    let address = pubkey(&snapshot["pubkey"]);
+    let account = &snapshot["account"];
+    assert_eq!(friendzy_client::id(), pubkey(&account["owner"]), "{name}");
+    assert_eq!("base64", account["data"][1].as_str().unwrap(), "{name}");
+
+    let data = decode_base64(account["data"][0].as_str().unwrap());
+    let label = label_account_data(&data).unwrap();
+    match label.kind {
+        AccountKind::Config => {
+            let config = Config::try_from_slice(&data).unwrap();
+            assert_eq!(derive_config_address(config.id).0, address, "{name}");
+            assert!(config.unclaimed <= config.royalties, "{name}");
+
+            // the float and integer forms of the curve agree to the lamport
+            let price = calculate_price(config.supply);
+            let cost = calculate_buy_cost(config.supply, DECIMAL_DENOMINATOR).unwrap();
+            assert!(price.abs_diff(cost) <= 1, "{name}: {price} != {cost}");
+        }
+        AccountKind::Profile => {
+            let profile = Profile::try_from_slice(&data).unwrap();
+            assert_eq!(
+                derive_profile_address(profile.id, &profile.owner).0,
+                address,
+                "{name}"
+            );
+        }
+        kind => panic!("{name}: unexpected account kind {kind:?}"),
+    }
+}
+
+#[test]
+fn test_replay_fixtures() -> Result<()> {
+    let recorded = recorded_fixtures();
+    for (name, fixture) in &recorded {
+        check_recorded(name, fixture);
+    }
+
+    let synthetic = synthetic_fixtures();
+    assert!(!synthetic.is_empty());
+
+    for (name, fixture) in recorded.iter().chain(&synthetic) {
+        let scenario = fixture["scenario"].as_str().unwrap();
+        assert!(SCENARIOS.contains(&scenario), "{name}: {scenario}");
+
+        for instruction in fixture["instructions"].as_array().unwrap() {
+            let expected = &instruction["expected"];
+            let first_purchase = &instruction["first_purchase"];
+            if let Some(data) = instruction["data"].as_str() {
+                let data = decode_base64(data);
+                check_instruction_data(name, &data, expected);
+                if instruction["account_keys"].is_array() {
+                    check_account_keys(name, &data, &instruction["account_keys"], first_purchase);
+                }
+            }
+            if instruction["accounts"].is_object() {
+                check_instruction_accounts(
+                    name,
+                    &instruction["accounts"],
+                    first_purchase,
+                    expected,
+                );
+            }
+        }
+
+        for account in fixture["accounts"].as_array().unwrap() {
+            check_account(name, account);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "no scenario has been recorded from a mainnet transaction yet"]
+fn test_fixtures_cover_scenarios() -> Result<()> {
+    let fixtures = recorded_fixtures();
+
+    for scenario in SCENARIOS {
+        assert!(
+            fixtures
+                .iter()
+                .any(|(_, fixture)| fixture["scenario"] == scenario),
+            "missing fixture for {scenario}"
+        );
+    }
+
+    Ok(())
+}
+
+/// Records the supply every trade leaves, without trading.
+struct SupplyRecorder(Vec<u64>);
+
+impl Strategy for SupplyRecorder {
+    fn on_trade(&mut self, _: &TradeEvent, curve: &CurveState, _: u64) -> Vec<Order> {
+        self.0.push(curve.supply);
+        vec![]
+    }
+}
+
+/// Buys one key after the first trade and holds it.
+struct BuyFirst;
+
+impl Strategy for BuyFirst {
+    fn on_trade(&mut self, trade: &TradeEvent, _: &CurveState, _: u64) -> Vec<Order> {
+        match trade.slot {
+            0 => vec![Order {
+                id: trade.id,
+                side: Side::Buy,
+                amount: DECIMAL_DENOMINATOR,
+            }],
+            _ => vec![],
+        }
+    }
+}
+
+/// The swaps of the fixtures as a trade history, one slot per swap in file order, recorded fixtures
+/// first.
+fn fixture_trades() -> Vec<TradeEvent> {
+    let mut trades = vec![];
+    for (_, fixture) in recorded_fixtures().into_iter().chain(synthetic_fixtures()) {
+        for instruction in fixture["instructions"].as_array().unwrap() {
+            let Some(data) = instruction["data"].as_str() else {
+                continue;
+            };
+            let signer = match (
+                instruction["account_keys"][0]["pubkey"].as_str(),
+                instruction["accounts"]["user"].as_str(),
+            ) {
+                (Some(user), _) | (None, Some(user)) => Pubkey::from_str(user).unwrap(),
+                (None, None) => Pubkey::default(),
+            };
+            let instruction = Instruction {
+                program_id: friendzy_client::id(),
+                accounts: vec![AccountMeta::new(signer, true)],
+                data: decode_base64(data),
+            };
+            let slot = trades.len() as u64;
+            trades.extend(TradeEvent::from_instruction(
+                slot,
+                0,
+                slot as i64,
+                &instruction,
+            ));
+        }
+    }
+    trades
+}
+
+#[test]
+fn test_backtest_fixture_trades() -> Result<()> {
+    let snapshot = recorded_fixtures()
+        .into_iter()
+        .chain(synthetic_fixtures())
+        .find(|(_, fixture)| fixture["scenario"] == "snapshot")
+        .unwrap()
+        .1;
+    let data = decode_base64(
+        snapshot["accounts"][0]["account"]["data"][0]
+            .as_str()
+            .unwrap(),
+    );
+    let config = Config::try_from_slice(&data).unwrap();
+
+    let trades: Vec<TradeEvent> = fixture_trades()
+        .into_iter()
+        .filter(|trade| trade.id == config.id)
+        .collect();
+    assert!(trades.len() >= 5);
+    assert!(trades.iter().any(|trade| trade.side == Side::Sell));
+
+    // replayed in reverse, the backtester still walks the supply path of the history
+    let mut reversed = trades.clone();
+    reversed.reverse();
+    let mut recorder = SupplyRecorder(vec![]);
+    let report = Backtester::new(FEES)
+        .with_supply(config.id, config.supply)
+        .run(&mut recorder, &reversed);
+    let path: Vec<u64> = supply_path(config.id, config.supply, &trades)
+        .iter()
+        .map(|point| point.supply)
+        .collect();
+    assert_eq!(path, recorder.0);
+    assert!(report.fills.is_empty());
+    assert_eq!(0, report.pnl);
+
+    // a key bought after the first trade is marked at what selling it at the end returns
+    let report = Backtester::new(FEES)
+        .with_supply(config.id, config.supply)
+        .run(&mut BuyFirst, &trades);
+
+    let buy = FEES.quote(Side::Buy, path[0], DECIMAL_DENOMINATOR).unwrap();
+    let end = path.last().unwrap() + DECIMAL_DENOMINATOR;
+    let sell = FEES.quote(Side::Sell, end, DECIMAL_DENOMINATOR).unwrap();
+    assert_eq!(
+        vec![buy],
+        report
+            .fills
+            .iter()
+            .map(|fill| fill.quote)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        HashMap::from([(config.id, DECIMAL_DENOMINATOR)]),
+        report.positions
+    );
+    assert_eq!(sell.total() as i128 - buy.total() as i128, report.pnl);
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "client")]
+// The tests return anchor's `Result` like the crate's own, its `Error` is larger than clippy allows.
+#![allow(clippy::result_large_err)]
+
+use anchor_lang::prelude::*;
+use friendzy_client::*;
+use serde_json::{json, Value};
+use solana_client::{rpc_client::RpcClient, rpc_filter::Memcmp, rpc_response::RpcKeyedAccount};
+use solana_sdk::account::Account;
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+const RPC_PORT: u16 = 18_899;
+
+/// Kills the validator when the test ends, even on failure.
+struct Validator(Child);
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+fn snapshots() -> Vec<Value> {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/synthetic/account_snapshots.json");
+    let fixture: Value = serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap();
+    fixture["accounts"].as_array().unwrap().clone()
+}
+
+/// The `--account` file of a snapshot, it needs the fields `getAccountInfo` would return.
+fn account_file(snapshot: &Value) -> Value {
+    json!({
+        "pubkey": snapshot["pubkey"],
+        "account": {
+            "lamports": 1_000_000_000u64,
+            "data": snapshot["account"]["data"],
+            "owner": snapshot["account"]["owner"],
+            "executable": false,
+            "rentEpoch": 0,
+        },
+    })
+}
+
+/// Starts `solana-test-validator` with the accounts of `account_snapshots.json` and waits for it.
+fn start_validator() -> (Validator, RpcClient, Vec<Value>) {
+    let accounts = snapshots();
+
+    let dir = std::env::temp_dir().join(format!("friendzy-scan-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut command = Command::new("solana-test-validator");
+    command
+        .arg("--reset")
+        .arg("--quiet")
+        .arg("--ledger")
+        .arg(dir.join("ledger"))
+        .arg("--rpc-port")
+        .arg(RPC_PORT.to_string())
+        .stdout(Stdio::null());
+
+    for (index, snapshot) in accounts.iter().enumerate() {
+        let path = dir.join(format!("account-{}.json", index));
+        fs::write(&path, account_file(snapshot).to_string()).unwrap();
+        command
+            .arg("--account")
+            .arg(snapshot["pubkey"].as_str().unwrap())
+            .arg(&path);
+    }
+
+    let validator = Validator(
+        command
+            .spawn()
+            .expect("solana-test-validator is not installed"),
+    );
+    let rpc_client = RpcClient::new(format!("http://127.0.0.1:{}", RPC_PORT));
+
+    let started = Instant::now();
+    while rpc_client.get_health().is_err() {
+        assert!(
+            started.elapsed() < Duration::from_secs(60),
+            "validator didn't start"
+        );
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    (validator, rpc_client, accounts)
+}
+
+/// The validator reads the `--account` files as keyed accounts, the owner filters of the scans
+/// must match the fixture data.
+#[test]
+fn test_account_files() -> Result<()> {
+    let owner = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+
+    for snapshot in snapshots() {
+        let file: RpcKeyedAccount = serde_json::from_value(account_file(&snapshot)).unwrap();
+        assert_eq!(snapshot["pubkey"], file.pubkey);
+        let account = file.account.decode::<Account>().unwrap();
+        assert_eq!(friendzy_client::id(), account.owner);
+
+        let offset = match account.data.len() {
+            Config::LEN => Config::OWNER_OFFSET,
+            Profile::LEN => Profile::OWNER_OFFSET,
+            len => panic!("unexpected account length {len}"),
+        };
+        assert!(Memcmp::new_base58_encoded(offset, owner.as_ref()).bytes_match(&account.data));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs solana-test-validator"]
+fn test_scan_fixture_accounts() -> Result<()> {
+    let (_validator, rpc_client, accounts) = start_validator();
+    let program = FriendzyProgram::MAINNET;
+    let owner = Pubkey::from_str("hoakwpFB8UoLnPpLC56gsjpY7XbVwaCuRQRMQzN5TVh").unwrap();
+    let addresses: Vec<Pubkey> = accounts
+        .iter()
+        .map(|account| Pubkey::from_str(account["pubkey"].as_str().unwrap()).unwrap())
+        .collect();
+
+    let configs: Vec<(Pubkey, Config)> = scan_configs(&rpc_client, &program)
+        .unwrap()
+        .collect::<std::result::Result<_, _>>()
+        .unwrap();
+    assert_eq!(1, configs.len());
+    assert_eq!(addresses[0], configs[0].0);
+    assert_eq!(1_162_302_698_118_684_672, configs[0].1.id);
+
+    let configs = scan_configs_by_owner(&rpc_client, &program, &owner).unwrap();
+    assert_eq!(1, configs.count());
+    let configs = scan_configs_by_owner(&rpc_client, &program, &Pubkey::new_unique()).unwrap();
+    assert_eq!(0, configs.count());
+
+    let profiles: Vec<(Pubkey, Profile)> = scan_profiles_by_owner(&rpc_client, &program, &owner)
+        .unwrap()
+        .collect::<std::result::Result<_, _>>()
+        .unwrap();
+    assert_eq!(1, profiles.len());
+    assert_eq!(addresses[1], profiles[0].0);
+    assert_eq!(44_000_000_000, profiles[0].1.buy_amount);
+    assert_eq!(1, scan_profiles(&rpc_client, &program).unwrap().count());
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use solana_program::program_error::ProgramError;
+
+/// The errors returned by the mock program as `ProgramError::Custom` codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MockError {
+    /// The quote exceeds `max_price` on a buy or is below `min_price` on a sell.
+    PriceBound = 0,
+    /// Selling more keys than the supply or the seller holds.
+    InsufficientSupply = 1,
+    /// Swapping an id that has no `Config` yet on a sell, or withdrawing from one.
+    UnknownId = 2,
+    /// Withdrawing from or re-verifying a `Config` the signer does not own.
+    NotOwner = 3,
+    /// A `Config` or `Profile` counter overflowed.
+    Overflow = 4,
+}
+
+impl From<MockError> for ProgramError {
+    fn from(error: MockError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
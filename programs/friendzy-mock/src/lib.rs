@@ -0,0 +1,27 @@
+//! A native mock of the Friendzy program.
+//!
+//! It implements the reverse-engineered semantics of the verify, swap and withdraw instructions
+//! over the same `Config` and `Profile` layouts, bank PDA and vault used by `friendzy-client`, so
+//! the client can be tested with `solana-program-test` or a local validator instead of mainnet.
+//!
+//! Things the mock does not reproduce:
+//! - the metadata account is never created, the Metaplex accounts are accepted but ignored
+//! - fees follow [`processor::FEES`], which are not confirmed on-chain
+//! - `Config::debt` and `Profile::reserved` are never written
+//! - verify accepts any signer as the verification authority of a new id
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+pub mod error;
+pub mod processor;
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    processor::process(program_id, accounts, instruction_data)
+}
@@ -0,0 +1,381 @@
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use friendzy_client::{Config, FeeSchedule, Profile, Side, SwapArgs, VerifyArgs, WithdrawArgs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::error::MockError;
+
+/// The decimals of every key mint, matching `friendzy_client::DECIMAL_DENOMINATOR`.
+pub const MINT_DECIMALS: u8 = 9;
+
+/// The fees charged by the mock, the 5% creator royalty and 5% protocol fee of friend.tech. The
+/// program's own rates have not been decoded yet.
+pub const FEES: FeeSchedule = FeeSchedule {
+    royalty_bps: 500,
+    protocol_bps: 500,
+};
+
+/// The offset of the instruction discriminator, right after the version byte and the id.
+const INSTRUCTION_INDEX: usize = 9;
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction = data
+        .get(INSTRUCTION_INDEX)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match instruction {
+        0 => {
+            msg!("Instruction: Verify");
+            let args = VerifyArgs::try_from_slice(data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            process_verify(program_id, accounts, args)
+        }
+        1 | 2 => {
+            msg!("Instruction: Swap");
+            let args =
+                SwapArgs::try_from_slice(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+            process_swap(program_id, accounts, args)
+        }
+        3 => {
+            msg!("Instruction: Withdraw");
+            let args = WithdrawArgs::try_from_slice(data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            process_withdraw(program_id, accounts, args)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Verify accounts: [authority (signer), config, system program]
+///
+/// Any signer can verify a new id, only the current owner can hand over an existing `Config`.
+fn process_verify(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: VerifyArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let id_bytes = args.id.to_le_bytes();
+    let config_bump = check_pda(program_id, config_info, &[b"config", &id_bytes])?;
+
+    let mut config = if config_info.data_is_empty() {
+        create_pda_account(
+            authority,
+            config_info,
+            system_program,
+            Config::LEN,
+            program_id,
+            &[b"config", &id_bytes, &[config_bump]],
+        )?;
+        Config {
+            id: args.id,
+            ..Default::default()
+        }
+    } else {
+        let config = Config::try_from_slice(&config_info.data.borrow())?;
+        if config.owner != *authority.key {
+            return Err(MockError::NotOwner.into());
+        }
+        config
+    };
+
+    config.owner = args.owner;
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Swap accounts, see `friendzy_client::swap`.
+fn process_swap(program_id: &Pubkey, accounts: &[AccountInfo], args: SwapArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let user = next_account_info(accounts_iter)?;
+    let bank = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let profile_info = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let _rent = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let _metadata = next_account_info(accounts_iter)?;
+    let _metadata_program = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let ata_program = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if args.side == Side::Default {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let id_bytes = args.id.to_le_bytes();
+    let bank_bump = check_pda(program_id, bank, &[b"bank"])?;
+    let config_bump = check_pda(program_id, config_info, &[b"config", &id_bytes])?;
+    let mint_bump = check_pda(program_id, mint, &[b"mint", &id_bytes])?;
+    let profile_bump = check_pda(
+        program_id,
+        profile_info,
+        &[b"config", &id_bytes, user.key.as_ref()],
+    )?;
+    let bank_seeds: &[&[u8]] = &[b"bank", &[bank_bump]];
+
+    if bank.data_is_empty() && bank.lamports() == 0 {
+        create_pda_account(user, bank, system_program, 0, program_id, bank_seeds)?;
+    }
+
+    let mut config = if config_info.data_is_empty() {
+        if args.side == Side::Sell {
+            return Err(MockError::UnknownId.into());
+        }
+        create_pda_account(
+            user,
+            config_info,
+            system_program,
+            Config::LEN,
+            program_id,
+            &[b"config", &id_bytes, &[config_bump]],
+        )?;
+        Config {
+            id: args.id,
+            ..Default::default()
+        }
+    } else {
+        Config::try_from_slice(&config_info.data.borrow())?
+    };
+
+    if mint.data_is_empty() {
+        create_pda_account(
+            user,
+            mint,
+            system_program,
+            spl_token::state::Mint::LEN,
+            token_program.key,
+            &[b"mint", &id_bytes, &[mint_bump]],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_mint2(
+                token_program.key,
+                mint.key,
+                bank.key,
+                None,
+                MINT_DECIMALS,
+            )?,
+            &[mint.clone(), token_program.clone()],
+        )?;
+    }
+
+    if token_account.data_is_empty() {
+        invoke(
+            &create_associated_token_account_idempotent(
+                user.key,
+                user.key,
+                mint.key,
+                token_program.key,
+            ),
+            &[
+                user.clone(),
+                token_account.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                ata_program.clone(),
+            ],
+        )?;
+    }
+
+    let mut profile = if profile_info.data_is_empty() {
+        create_pda_account(
+            user,
+            profile_info,
+            system_program,
+            Profile::LEN,
+            program_id,
+            &[b"config", &id_bytes, user.key.as_ref(), &[profile_bump]],
+        )?;
+        Profile {
+            id: args.id,
+            owner: *user.key,
+            ..Default::default()
+        }
+    } else {
+        Profile::try_from_slice(&profile_info.data.borrow())?
+    };
+
+    let quote = FEES
+        .quote(args.side, config.supply, args.amount)
+        .ok_or(MockError::InsufficientSupply)?;
+    if !quote.within_bound(args.price) {
+        msg!("Quote {} outside of bound {}", quote.total(), args.price);
+        return Err(MockError::PriceBound.into());
+    }
+
+    match args.side {
+        Side::Buy => {
+            invoke(
+                &system_instruction::transfer(user.key, bank.key, quote.cost + quote.royalty),
+                &[user.clone(), bank.clone(), system_program.clone()],
+            )?;
+            invoke(
+                &system_instruction::transfer(user.key, vault.key, quote.protocol_fee),
+                &[user.clone(), vault.clone(), system_program.clone()],
+            )?;
+            invoke_signed(
+                &spl_token::instruction::mint_to(
+                    token_program.key,
+                    mint.key,
+                    token_account.key,
+                    bank.key,
+                    &[],
+                    args.amount,
+                )?,
+                &[
+                    mint.clone(),
+                    token_account.clone(),
+                    bank.clone(),
+                    token_program.clone(),
+                ],
+                &[bank_seeds],
+            )?;
+
+            config.supply = checked_add(config.supply, args.amount)?;
+            profile.buy_amount = checked_add(profile.buy_amount, args.amount)?;
+            profile.buy_volume = checked_add(profile.buy_volume, quote.cost)?;
+        }
+        _ => {
+            if profile.net_amount().unwrap_or(0) < args.amount {
+                msg!("Selling {} keys the profile does not hold", args.amount);
+                return Err(MockError::InsufficientSupply.into());
+            }
+
+            invoke(
+                &spl_token::instruction::burn(
+                    token_program.key,
+                    token_account.key,
+                    mint.key,
+                    user.key,
+                    &[],
+                    args.amount,
+                )?,
+                &[
+                    token_account.clone(),
+                    mint.clone(),
+                    user.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            // the royalty stays in the bank until the owner withdraws it
+            transfer_from_bank(bank, user, quote.total())?;
+            transfer_from_bank(bank, vault, quote.protocol_fee)?;
+
+            config.supply -= args.amount;
+            profile.sell_amount = checked_add(profile.sell_amount, args.amount)?;
+            profile.sell_volume = checked_add(profile.sell_volume, quote.cost)?;
+        }
+    }
+
+    config.royalties = checked_add(config.royalties, quote.royalty)?;
+    config.unclaimed = checked_add(config.unclaimed, quote.royalty)?;
+
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+    profile.serialize(&mut &mut profile_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Withdraw accounts, see `friendzy_client::withdraw`.
+fn process_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: WithdrawArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let user = next_account_info(accounts_iter)?;
+    let bank = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let id_bytes = args.id.to_le_bytes();
+    check_pda(program_id, bank, &[b"bank"])?;
+    check_pda(program_id, config_info, &[b"config", &id_bytes])?;
+
+    if config_info.data_is_empty() {
+        return Err(MockError::UnknownId.into());
+    }
+    let mut config = Config::try_from_slice(&config_info.data.borrow())?;
+    if config.owner != *user.key {
+        return Err(MockError::NotOwner.into());
+    }
+
+    transfer_from_bank(bank, user, config.unclaimed)?;
+    config.unclaimed = 0;
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Checks the account is the PDA for the seeds and returns its bump.
+fn check_pda(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+) -> Result<u8, ProgramError> {
+    let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+    if address != *account.key {
+        msg!("Invalid PDA {}, expected {}", account.key, address);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    invoke_signed(
+        &system_instruction::create_account(payer.key, account.key, lamports, space as u64, owner),
+        &[payer.clone(), account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+fn checked_add(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_add(b).ok_or_else(|| MockError::Overflow.into())
+}
+
+fn transfer_from_bank(bank: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let remaining = bank
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **bank.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
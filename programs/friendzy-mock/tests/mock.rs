@@ -0,0 +1,337 @@
+use anchor_lang::AnchorDeserialize;
+use friendzy_client::*;
+use friendzy_mock::processor::FEES;
+use solana_program::{instruction::AccountMeta, system_program};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::get_associated_token_address;
+
+const ID: u64 = 1_436_880_221_354_045_450;
+const ONE_KEY: u64 = DECIMAL_DENOMINATOR;
+const LAMPORTS: u64 = 100 * DECIMAL_DENOMINATOR;
+
+struct Context {
+    banks: BanksClient,
+    program: FriendzyProgram,
+    creator: Keypair,
+    user: Keypair,
+}
+
+async fn setup() -> Context {
+    let program = FriendzyProgram::new(Pubkey::new_unique(), Pubkey::new_unique());
+    let creator = Keypair::new();
+    let user = Keypair::new();
+
+    let mut test = ProgramTest::new(
+        "friendzy_mock",
+        program.program_id,
+        processor!(friendzy_mock::process_instruction),
+    );
+    for address in [program.vault, creator.pubkey(), user.pubkey()] {
+        test.add_account(
+            address,
+            Account {
+                lamports: LAMPORTS,
+                owner: system_program::ID,
+                ..Account::default()
+            },
+        );
+    }
+
+    let (banks, _, _) = test.start().await;
+
+    Context {
+        banks,
+        program,
+        creator,
+        user,
+    }
+}
+
+async fn send(
+    context: &mut Context,
+    ix: Instruction,
+    signer: &Keypair,
+) -> Result<(), TransactionError> {
+    let blockhash = context.banks.get_latest_blockhash().await.unwrap();
+    let tx =
+        Transaction::new_signed_with_payer(&[ix], Some(&signer.pubkey()), &[signer], blockhash);
+    context
+        .banks
+        .process_transaction(tx)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+fn verify_ix(program: &FriendzyProgram, authority: &Pubkey, owner: &Pubkey) -> Instruction {
+    let (config, _) = program.derive_config_address(ID);
+    Instruction {
+        program_id: program.program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: create_verify_instruction_data(owner, ID),
+    }
+}
+
+fn swap_ix(
+    program: &FriendzyProgram,
+    user: &Pubkey,
+    first_purchase: bool,
+    amount: u64,
+    price: u64,
+    side: Side,
+) -> Instruction {
+    program.swap(
+        &program.swap_accounts(ID, user),
+        first_purchase,
+        ID,
+        amount,
+        price,
+        side,
+    )
+}
+
+async fn load_config(context: &mut Context) -> Config {
+    let (config, _) = context.program.derive_config_address(ID);
+    let account = context.banks.get_account(config).await.unwrap().unwrap();
+    Config::try_from_slice(&account.data).unwrap()
+}
+
+async fn load_profile(context: &mut Context, user: &Pubkey) -> Profile {
+    let (profile, _) = context.program.derive_profile_address(ID, user);
+    let account = context.banks.get_account(profile).await.unwrap().unwrap();
+    Profile::try_from_slice(&account.data).unwrap()
+}
+
+async fn token_balance(context: &mut Context, user: &Pubkey) -> u64 {
+    let (mint, _) = context.program.derive_mint_address(ID);
+    let address = get_associated_token_address(user, &mint);
+    let account = context.banks.get_account(address).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+#[tokio::test]
+async fn test_verify_buy_sell_withdraw() {
+    let mut context = setup().await;
+    let program = context.program;
+    let creator = context.creator.insecure_clone();
+    let user = context.user.insecure_clone();
+    let fees = FEES;
+
+    let ix = verify_ix(&program, &creator.pubkey(), &creator.pubkey());
+    send(&mut context, ix, &creator).await.unwrap();
+    let config = load_config(&mut context).await;
+    assert_eq!(ID, config.id);
+    assert_eq!(creator.pubkey(), config.owner);
+    assert_eq!(0, config.supply);
+
+    // first purchase of two keys, the quote total is the tightest possible max price
+    let buy = fees.quote(Side::Buy, 0, 2 * ONE_KEY).unwrap();
+    let vault_before = context.banks.get_balance(program.vault).await.unwrap();
+    let ix = swap_ix(
+        &program,
+        &user.pubkey(),
+        true,
+        2 * ONE_KEY,
+        buy.total(),
+        Side::Buy,
+    );
+    send(&mut context, ix, &user).await.unwrap();
+
+    let config = load_config(&mut context).await;
+    assert_eq!(2 * ONE_KEY, config.supply);
+    assert_eq!(buy.royalty, config.royalties);
+    assert_eq!(buy.royalty, config.unclaimed);
+
+    let profile = load_profile(&mut context, &user.pubkey()).await;
+    assert_eq!(user.pubkey(), profile.owner);
+    assert_eq!(2 * ONE_KEY, profile.buy_amount);
+    assert_eq!(buy.cost, profile.buy_volume);
+    assert_eq!(
+        2 * ONE_KEY,
+        token_balance(&mut context, &user.pubkey()).await
+    );
+
+    let vault_after = context.banks.get_balance(program.vault).await.unwrap();
+    assert_eq!(buy.protocol_fee, vault_after - vault_before);
+
+    // repeat buy with a max price one lamport short of the quote
+    let repeat = fees.quote(Side::Buy, 2 * ONE_KEY, ONE_KEY).unwrap();
+    let ix = swap_ix(
+        &program,
+        &user.pubkey(),
+        false,
+        ONE_KEY,
+        repeat.total() - 1,
+        Side::Buy,
+    );
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(0)),
+        err
+    );
+
+    // sell one key back at the quoted minimum
+    let sell = fees.quote(Side::Sell, 2 * ONE_KEY, ONE_KEY).unwrap();
+    let user_before = context.banks.get_balance(user.pubkey()).await.unwrap();
+    let ix = swap_ix(
+        &program,
+        &user.pubkey(),
+        false,
+        ONE_KEY,
+        sell.total(),
+        Side::Sell,
+    );
+    send(&mut context, ix, &user).await.unwrap();
+    let user_after = context.banks.get_balance(user.pubkey()).await.unwrap();
+    // the user pays the transaction fee out of the proceeds
+    assert!(user_after > user_before);
+
+    let config = load_config(&mut context).await;
+    assert_eq!(ONE_KEY, config.supply);
+    assert_eq!(buy.royalty + sell.royalty, config.unclaimed);
+    let profile = load_profile(&mut context, &user.pubkey()).await;
+    assert_eq!(ONE_KEY, profile.sell_amount);
+    assert_eq!(sell.cost, profile.sell_volume);
+    assert_eq!(ONE_KEY, token_balance(&mut context, &user.pubkey()).await);
+
+    // only the owner can withdraw the royalties
+    let (bank, _) = program.derive_bank_address();
+    let (mint, _) = program.derive_mint_address(ID);
+    let (config_address, _) = program.derive_config_address(ID);
+    let (profile_address, _) = program.derive_profile_address(ID, &user.pubkey());
+    let ix = program.withdraw(
+        &user.pubkey(),
+        &bank,
+        &config_address,
+        &mint,
+        &profile_address,
+        ID,
+    );
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(3)),
+        err
+    );
+
+    let (creator_profile, _) = program.derive_profile_address(ID, &creator.pubkey());
+    let ix = program.withdraw(
+        &creator.pubkey(),
+        &bank,
+        &config_address,
+        &mint,
+        &creator_profile,
+        ID,
+    );
+    send(&mut context, ix, &creator).await.unwrap();
+    let config = load_config(&mut context).await;
+    assert_eq!(0, config.unclaimed);
+    assert_eq!(buy.royalty + sell.royalty, config.claimed_royalties());
+}
+
+#[tokio::test]
+async fn test_sell_unknown_id_fails() {
+    let mut context = setup().await;
+    let program = context.program;
+    let user = context.user.insecure_clone();
+
+    let ix = swap_ix(&program, &user.pubkey(), false, ONE_KEY, 0, Side::Sell);
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(2)),
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_verify_by_other_signer_fails() {
+    let mut context = setup().await;
+    let program = context.program;
+    let creator = context.creator.insecure_clone();
+    let user = context.user.insecure_clone();
+
+    let ix = verify_ix(&program, &creator.pubkey(), &creator.pubkey());
+    send(&mut context, ix, &creator).await.unwrap();
+
+    // only the owner can hand the config over
+    let ix = verify_ix(&program, &user.pubkey(), &user.pubkey());
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(3)),
+        err
+    );
+    assert_eq!(creator.pubkey(), load_config(&mut context).await.owner);
+
+    let ix = verify_ix(&program, &creator.pubkey(), &user.pubkey());
+    send(&mut context, ix, &creator).await.unwrap();
+    assert_eq!(user.pubkey(), load_config(&mut context).await.owner);
+}
+
+#[tokio::test]
+async fn test_sell_more_than_held_fails() {
+    let mut context = setup().await;
+    let program = context.program;
+    let creator = context.creator.insecure_clone();
+    let user = context.user.insecure_clone();
+
+    let buy = FEES.quote(Side::Buy, 0, ONE_KEY).unwrap();
+    let ix = swap_ix(
+        &program,
+        &user.pubkey(),
+        true,
+        ONE_KEY,
+        buy.total(),
+        Side::Buy,
+    );
+    send(&mut context, ix, &user).await.unwrap();
+
+    // the supply covers the sell but the seller holds no keys
+    let ix = swap_ix(&program, &creator.pubkey(), false, ONE_KEY, 0, Side::Sell);
+    let err = send(&mut context, ix, &creator).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(1)),
+        err
+    );
+
+    let ix = swap_ix(&program, &user.pubkey(), false, 2 * ONE_KEY, 0, Side::Sell);
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::Custom(1)),
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_swap_with_mainnet_addresses_fails() {
+    let mut context = setup().await;
+    let user = context.user.insecure_clone();
+
+    // addresses derived for mainnet don't belong to the local deployment
+    let mut ix = swap_ix(
+        &FriendzyProgram::MAINNET,
+        &user.pubkey(),
+        true,
+        ONE_KEY,
+        u64::MAX,
+        Side::Buy,
+    );
+    ix.program_id = context.program.program_id;
+    let err = send(&mut context, ix, &user).await.unwrap_err();
+    assert_eq!(
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds),
+        err
+    );
+}